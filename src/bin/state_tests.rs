@@ -0,0 +1,179 @@
+use std::{
+    any::Any,
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use evm_from_scratch_new::evm::{
+    evm::EVM,
+    utils::{
+        errors::EVMError,
+        helpers::logs_hash,
+        spec::SpecId,
+        state_test_types::{parse_u256, PostState, StateTest},
+        types::{BlockHeader, ExecutionContext},
+    },
+};
+
+thread_local! {
+    /// where the last panic took place, recorded by the panic hook
+    static PANIC_LOCATION: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Runs the GeneralStateTests fixtures of ethereum/tests found in the
+/// directory given as first argument, optionally only for the hard fork
+/// given as second argument, and reports the outcome for each fork
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let Some(directory) = args.next() else {
+        eprintln!("Usage: state_tests <directory> [fork]");
+        std::process::exit(2);
+    };
+    let only_fork = args.next();
+
+    let mut files = Vec::new();
+    collect_json_files(Path::new(&directory), &mut files);
+    files.sort();
+
+    // a panic is reported as a failure of the test which caused it,
+    // instead of being printed by the default hook
+    panic::set_hook(Box::new(|info| {
+        let location = info.location().map(|location| location.to_string());
+        PANIC_LOCATION.with(|last_location| *last_location.borrow_mut() = location);
+    }));
+
+    // the number of passed and run tests for each fork
+    let mut results: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    let mut skipped_forks: BTreeMap<String, usize> = BTreeMap::new();
+
+    for file in files {
+        let text = std::fs::read_to_string(&file).unwrap();
+        let tests: HashMap<String, StateTest> = match serde_json::from_str(&text) {
+            Ok(tests) => tests,
+            Err(e) => {
+                println!("SKIP {}: {}", file.display(), e);
+                continue;
+            }
+        };
+
+        for (name, test) in &tests {
+            for (fork, post_states) in &test.post {
+                if only_fork
+                    .as_ref()
+                    .is_some_and(|only_fork| only_fork != fork)
+                {
+                    continue;
+                }
+                let Some(spec) = SpecId::from_fork_name(fork) else {
+                    *skipped_forks.entry(fork.clone()).or_default() += post_states.len();
+                    continue;
+                };
+
+                for post_state in post_states {
+                    let indexes = &post_state.indexes;
+                    let outcome = panic::catch_unwind(AssertUnwindSafe(|| {
+                        run_state_test(test, spec, post_state)
+                    }))
+                    .unwrap_or_else(|payload| Err(panic_message(payload)));
+
+                    let (passed, total) = results.entry(fork.clone()).or_default();
+                    *total += 1;
+                    match outcome {
+                        Ok(()) => *passed += 1,
+                        Err(reason) => println!(
+                            "FAIL {} {} d{} g{} v{}: {}",
+                            name, fork, indexes.data, indexes.gas, indexes.value, reason
+                        ),
+                    }
+                }
+            }
+        }
+    }
+
+    println!();
+    for (fork, (passed, total)) in &results {
+        println!("{}: {}/{} passed", fork, passed, total);
+    }
+    for (fork, count) in &skipped_forks {
+        println!("{}: {} skipped, fork not supported", fork, count);
+    }
+}
+
+/// Executes the transaction of the matrix selected by `post_state`
+/// and compares its outcome with the expected one
+fn run_state_test(test: &StateTest, spec: SpecId, post_state: &PostState) -> Result<(), String> {
+    let mut ctx = ExecutionContext::new();
+    ctx.spec = spec;
+    ctx.input.block_header = BlockHeader::from(&test.env);
+
+    // the accounts of the `pre` state are read from the database
+    // the first time they are accessed, as they would be from a node
+    ctx.global_state.clear();
+    ctx.db = Arc::new(test.database());
+
+    let tx = test.transaction.transaction(&post_state.indexes);
+
+    let logs = match (EVM::transact(&mut ctx, &tx), &post_state.expect_exception) {
+        // an invalid transaction leaves the state untouched
+        (Err(EVMError::InvalidTransactionError(_)), Some(_)) => Vec::new(),
+        (Err(e), _) => return Err(e.to_string()),
+        (Ok(_), Some(exception)) => return Err(format!("expected exception {}", exception)),
+        (Ok(result), None) => result.logs,
+    };
+
+    let expected_state_root = parse_u256(&post_state.hash);
+    let actual_state_root = ctx
+        .state_root()
+        .ok_or_else(|| String::from("the world state is not known"))?;
+    if actual_state_root != expected_state_root {
+        return Err(format!(
+            "state root {:#x}, expected {:#x}",
+            actual_state_root, expected_state_root
+        ));
+    }
+
+    let expected_logs_hash = parse_u256(&post_state.logs);
+    let actual_logs_hash = logs_hash(&logs);
+    if actual_logs_hash != expected_logs_hash {
+        return Err(format!(
+            "logs hash {:#x}, expected {:#x}",
+            actual_logs_hash, expected_logs_hash
+        ));
+    }
+
+    Ok(())
+}
+
+/// Describes the panic whose payload is `payload`,
+/// with the message passed to `panic!` and its location
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    let message = if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        String::from("unknown payload")
+    };
+
+    match PANIC_LOCATION.with(|last_location| last_location.borrow_mut().take()) {
+        Some(location) => format!("panicked at {}: {}", location, message),
+        None => format!("panicked: {}", message),
+    }
+}
+
+/// Appends to `files` the JSON files in `path`, searched recursively
+fn collect_json_files(path: &Path, files: &mut Vec<PathBuf>) {
+    if path.is_dir() {
+        for entry in std::fs::read_dir(path).unwrap() {
+            collect_json_files(&entry.unwrap().path(), files);
+        }
+    } else if path
+        .extension()
+        .is_some_and(|extension| extension == "json")
+    {
+        files.push(path.to_path_buf());
+    }
+}
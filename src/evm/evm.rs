@@ -1,55 +1,98 @@
+use std::{collections::HashMap, sync::Arc};
+
+use primitive_types::U256;
+
 use crate::evm::utils::constants::REVERT;
 
-use super::utils::constants::{INVALID, NO_STATIC_OPCODES};
-use super::utils::types::EVMReturnData;
+use super::precompiles::get_precompiles;
+use super::utils::constants::{
+    EOF_MAGIC, INVALID, KECCAK_EMPTY, MAX_CODE_SIZE, NO_STATIC_OPCODES, STACK_LIMIT,
+};
+use super::utils::gas::{capped_refund, charge_gas, static_cost, G_CODEDEPOSIT};
+use super::utils::helpers::{create_address, transfer};
+use super::utils::spec::SpecId;
+use super::utils::transaction::{Transaction, TransactionResult};
+use super::utils::types::{AccountState, AccruedSubstate, EVMReturnData, Input, MachineState};
 use super::utils::{errors::EVMError, helpers::get_opcodes, types::ExecutionContext};
 
 pub struct EVM {}
 
 impl EVM {
-    pub fn execute(ctx: &mut ExecutionContext) -> Result<EVMReturnData, EVMError> {
-        let opcodes = get_opcodes();
+    /// Runs `ctx.input.bytecode` with the gas in `ctx.machine_state`. An error
+    /// raised by an opcode is an exceptional halt, so the execution never fails
+    pub fn execute(ctx: &mut ExecutionContext) -> EVMReturnData {
+        let opcodes = get_opcodes(ctx.spec);
+        let gas_limit = ctx.machine_state.gas;
         let mut output = None;
         let mut success = true;
 
+        // the accounts of the context are read by opcodes like SELFBALANCE
+        // and CREATE without being accessed through an address on the stack
+        ctx.load_account(ctx.input.address);
+        ctx.load_account(ctx.input.origin);
+        ctx.load_account(ctx.input.sender);
+
+        if ctx.input.depth == 0 {
+            // a transaction is starting
+            ctx.accrued_substate.warm_up(&ctx.input, ctx.spec);
+        }
+
+        // sub-contexts are reverted by their caller, which may
+        // have modified the state before running them
+        let checkpoint = if ctx.input.depth == 0 {
+            Some(ctx.checkpoint())
+        } else {
+            None
+        };
+
         while ctx.machine_state.pc < ctx.input.bytecode.len() {
-            let opcode = ctx
-                .input
-                .bytecode
-                .get(ctx.machine_state.pc)
-                .ok_or(EVMError::NoBytecodeError(ctx.clone()))?;
+            let opcode = ctx.input.bytecode[ctx.machine_state.pc];
 
-            match *opcode {
+            match opcode {
                 REVERT => {
                     success = false;
                 }
                 INVALID => {
+                    // an exceptional halt consumes all the gas
                     success = false;
+                    ctx.machine_state.gas = U256::zero();
                     break;
                 }
                 _ => {}
             }
 
-            if NO_STATIC_OPCODES.contains(opcode) && !ctx.input.write {
+            if NO_STATIC_OPCODES.contains(&opcode) && !ctx.input.write {
                 success = false;
+                ctx.machine_state.gas = U256::zero();
                 break;
             }
 
-            let runner = opcodes
-                .get(opcode)
-                .ok_or(EVMError::NoOpcodeError(*opcode, ctx.clone()))?;
+            // an opcode which does not exist under the current spec
+            // is an exceptional halt, like INVALID
+            let Some(runner) = opcodes.get(&opcode) else {
+                success = false;
+                ctx.machine_state.gas = U256::zero();
+                break;
+            };
 
-            output = match runner(ctx) {
+            // any error raised by the opcode, like running out of gas or
+            // popping from an empty stack, is an exceptional halt
+            output = match charge_gas(ctx, static_cost(opcode, ctx.spec)).and_then(|_| runner(ctx))
+            {
                 Ok(option) => option,
-                Err(e) => match e {
-                    EVMError::InvalidJumpdestError(_, _) => {
-                        success = false;
-                        break;
-                    }
-                    _ => return Err(e),
-                },
+                Err(_) => {
+                    success = false;
+                    ctx.machine_state.gas = U256::zero();
+                    break;
+                }
             };
 
+            if ctx.machine_state.stack.len() > STACK_LIMIT {
+                success = false;
+                ctx.machine_state.gas = U256::zero();
+                break;
+            }
+
             ctx.machine_state.pc += 1;
 
             if let Some(_data) = &output {
@@ -57,13 +100,260 @@ impl EVM {
             }
         }
 
-        ctx.accrued_substate
-            .self_destruct_set
-            .iter()
-            .for_each(|account| {
-                ctx.global_state.remove(account);
-            });
+        if let (false, Some(checkpoint)) = (success, checkpoint) {
+            ctx.revert_to(checkpoint);
+        }
+
+        let gas_used = gas_limit - ctx.machine_state.gas;
+        let gas_refunded = if ctx.input.depth == 0 && success {
+            capped_refund(ctx.accrued_substate.refund_balance, gas_used, ctx.spec)
+        } else {
+            U256::zero()
+        };
+
+        EVMReturnData {
+            success,
+            output,
+            gas_used,
+            gas_refunded,
+        }
+    }
+
+    /// Runs the message call whose environment is `ctx.input` with the gas
+    /// in `ctx.machine_state`, executing either the precompiled contract
+    /// at `code_address` or the code in `ctx.input.bytecode`
+    pub fn execute_call(ctx: &mut ExecutionContext, code_address: U256) -> EVMReturnData {
+        let gas_limit = ctx.machine_state.gas;
+
+        match get_precompiles(ctx.spec).get(&code_address) {
+            Some(precompile) => match precompile(&ctx.input.data, gas_limit) {
+                Some(precompile_output) => EVMReturnData {
+                    output: Some(precompile_output.output),
+                    success: true,
+                    gas_used: precompile_output.gas_used,
+                    gas_refunded: U256::zero(),
+                },
+                None => EVMReturnData {
+                    output: None,
+                    success: false,
+                    gas_used: gas_limit,
+                    gas_refunded: U256::zero(),
+                },
+            },
+            None => EVM::execute(ctx),
+        }
+    }
+
+    /// Creates the account at `ctx.input.address`, endowed with `ctx.input.value`
+    /// by `ctx.input.sender`, then runs `ctx.input.bytecode` as its
+    /// initialisation code and deploys the code it returns.
+    /// The creation is reverted if it fails
+    pub fn execute_create(ctx: &mut ExecutionContext) -> EVMReturnData {
+        let gas_limit = ctx.machine_state.gas;
+        let new_account_address = ctx.input.address;
+        ctx.load_account(new_account_address);
+
+        // an account with code, nonce or storage cannot be overwritten,
+        // and the gas given to the creation is lost
+        // the storage is also looked up in the database, since
+        // only the slots which were accessed have been read
+        let collision = ctx
+            .global_state
+            .get(&new_account_address)
+            .map(|account_state| account_state.nonce != 0 || account_state.has_code())
+            .unwrap_or(false)
+            || ctx.has_storage(new_account_address);
+        if collision {
+            return EVMReturnData {
+                output: None,
+                success: false,
+                gas_used: gas_limit,
+                gas_refunded: U256::zero(),
+            };
+        }
+
+        let checkpoint = ctx.checkpoint();
+
+        // a pre-existing balance at the new address is kept. The nonce of
+        // a new contract starts from one from Spurious Dragon (EIP-161)
+        let nonce = if ctx.spec.is_enabled(SpecId::SpuriousDragon) {
+            1
+        } else {
+            0
+        };
+        let existing_balance = ctx
+            .global_state
+            .get(&new_account_address)
+            .map(|account_state| account_state.balance)
+            .unwrap_or(U256::zero());
+        ctx.create_account(
+            new_account_address,
+            AccountState {
+                nonce,
+                balance: existing_balance,
+                code_hash: KECCAK_EMPTY,
+                storage: HashMap::new(),
+            },
+        );
+        transfer(ctx, ctx.input.sender, new_account_address, ctx.input.value);
+
+        let result = EVM::execute(ctx);
+
+        let output = result.output.as_deref().unwrap_or(&[]);
+        let mut gas_used = result.gas_used;
+
+        // the code cannot start with 0xef from London (EIP-3541), nor be
+        // larger than the maximum size from Spurious Dragon (EIP-170)
+        let invalid_code = (ctx.spec.is_enabled(SpecId::London)
+            && output.first() == Some(&EOF_MAGIC))
+            || (ctx.spec.is_enabled(SpecId::SpuriousDragon) && output.len() > MAX_CODE_SIZE);
+
+        // the code deposit cost is paid with the gas left in the sub-context,
+        // if there is not enough of it the creation fails. Before Homestead,
+        // the contract is created without code instead (EIP-2)
+        let code_deposit_cost = U256::from(G_CODEDEPOSIT) * output.len();
+        let mut success = result.success;
+        let mut code = Vec::new();
+        if result.success {
+            if invalid_code {
+                success = false;
+                gas_used = gas_limit;
+            } else if gas_limit - gas_used >= code_deposit_cost {
+                gas_used += code_deposit_cost;
+                code = output.to_vec();
+            } else if ctx.spec.is_enabled(SpecId::Homestead) {
+                success = false;
+                gas_used = gas_limit;
+            }
+        }
+
+        if success {
+            // the new account's code is set to the return data
+            ctx.set_code(new_account_address, code);
+        } else {
+            ctx.revert_to(checkpoint);
+        }
+
+        // the return data is only kept when the initialisation code reverts,
+        // and not when the code it returns is deployed or cannot be
+        let output = if result.success { None } else { result.output };
+
+        EVMReturnData {
+            output,
+            success,
+            gas_used,
+            gas_refunded: result.gas_refunded,
+        }
+    }
+
+    /// Executes `tx` on top of the state in `ctx`, in the block whose header
+    /// is `ctx.input.block_header`: the sender pays the intrinsic gas and the
+    /// message call or the contract creation is run with the rest of the
+    /// gas limit. Then the unused gas is refunded to the sender, the priority
+    /// fee is paid to the beneficiary and the base fee is burnt.
+    /// An invalid transaction fails without modifying the state, while
+    /// a valid one is always executed, even if its execution fails
+    pub fn transact(
+        ctx: &mut ExecutionContext,
+        tx: &Transaction,
+    ) -> Result<TransactionResult, EVMError> {
+        let spec = ctx.spec;
+        let block_header = ctx.input.block_header.clone();
+
+        ctx.load_account(tx.sender);
+        ctx.load_account(block_header.beneficiary);
+        if let Some(to) = tx.to {
+            ctx.load_account(to);
+        }
+
+        tx.validate(&ctx.global_state, &block_header, spec)
+            .map_err(EVMError::InvalidTransactionError)?;
+
+        // the base fee is only burnt from London (EIP-1559)
+        let base_fee = if spec.is_enabled(SpecId::London) {
+            block_header.base_fee
+        } else {
+            U256::zero()
+        };
+        let gas_price = tx.effective_gas_price(base_fee);
+
+        // the whole gas limit is paid in advance, and the blob gas is burnt
+        let sender_state = ctx.account_mut(tx.sender);
+        sender_state.balance -=
+            tx.gas_limit * gas_price + tx.blob_gas() * block_header.blob_base_fee;
+        sender_state.nonce += 1;
+
+        ctx.accrued_substate = AccruedSubstate::new();
+        ctx.original_storage.clear();
+        for (address, keys) in tx.access_list() {
+            ctx.accrued_substate.accessed_accounts.insert(address);
+            for key in keys {
+                ctx.accrued_substate
+                    .accessed_storage_keys
+                    .insert((address, key));
+            }
+        }
+
+        ctx.input = Input {
+            address: tx.to.unwrap_or(create_address(tx.sender, tx.nonce)),
+            origin: tx.sender,
+            price: gas_price,
+            data: tx.data.clone(),
+            sender: tx.sender,
+            value: tx.value,
+            block_header: block_header.clone(),
+            bytecode: Arc::from([]),
+            depth: 0,
+            write: true,
+            blob_hashes: tx.blob_hashes(),
+        };
+        let execution_gas = tx.gas_limit - tx.intrinsic_gas(spec);
+        ctx.machine_state = MachineState::new();
+        ctx.machine_state.gas = execution_gas;
+
+        let result = match tx.to {
+            Some(to) => {
+                let checkpoint = ctx.checkpoint();
+                transfer(ctx, tx.sender, to, tx.value);
+                ctx.input.bytecode = ctx.code(to);
+
+                let result = EVM::execute_call(ctx, to);
+                if !result.success {
+                    ctx.revert_to(checkpoint);
+                }
+                result
+            }
+            None => {
+                ctx.input.bytecode = std::mem::take(&mut ctx.input.data).into();
+                EVM::execute_create(ctx)
+            }
+        };
+
+        // the refund is capped by the gas used by the whole transaction
+        let gas_left = execution_gas - result.gas_used;
+        let mut gas_used = tx.gas_limit - gas_left;
+        gas_used -= capped_refund(ctx.accrued_substate.refund_balance, gas_used, spec);
+
+        ctx.account_mut(tx.sender).balance += (tx.gas_limit - gas_used) * gas_price;
+        let beneficiary = block_header.beneficiary;
+        ctx.account_mut(beneficiary).balance += gas_used * (gas_price - base_fee);
+        ctx.touch_account(beneficiary);
+
+        let logs = ctx.accrued_substate.logs.clone();
+        ctx.finalise();
+
+        // the address of the new contract is only returned if it was deployed
+        let contract_address = match tx.to {
+            None if result.success => Some(ctx.input.address),
+            _ => None,
+        };
 
-        Ok(EVMReturnData { success, output })
+        Ok(TransactionResult {
+            success: result.success,
+            output: result.output.unwrap_or(Vec::new()),
+            gas_used,
+            logs,
+            contract_address,
+        })
     }
 }
@@ -1,3 +1,4 @@
 pub mod evm;
 pub mod opcodes;
+pub mod precompiles;
 pub mod utils;
@@ -1,8 +1,8 @@
 use primitive_types::U256;
 
 use crate::evm::utils::{
-    constants::CHAIN_ID,
     helpers::pop_n,
+    spec::SpecId,
     types::{ExecutionContext, OpcodeResult},
 };
 
@@ -10,10 +10,10 @@ use crate::evm::utils::{
 // block opcodes to see what should be inside
 
 /// 0x40
-/// Not implemented for this test suite
 pub fn blockhash(ctx: &mut ExecutionContext) -> OpcodeResult {
-    let _block_number = pop_n(ctx, 1)?[0];
-    ctx.machine_state.stack.push(U256::zero());
+    let block_number = pop_n(ctx, 1)?[0];
+    let hash = ctx.block_hash(block_number);
+    ctx.machine_state.stack.push(hash);
     Ok(None)
 }
 
@@ -39,9 +39,15 @@ pub fn number(ctx: &mut ExecutionContext) -> OpcodeResult {
 }
 
 /// 0x44
+/// Returns PREVRANDAO from Paris (EIP-4399), DIFFICULTY before
 pub fn difficulty(ctx: &mut ExecutionContext) -> OpcodeResult {
-    let difficulty = ctx.input.block_header.difficulty;
-    ctx.machine_state.stack.push(difficulty);
+    let block_header = &ctx.input.block_header;
+    let value = if ctx.spec.is_enabled(SpecId::Paris) {
+        block_header.prevrandao
+    } else {
+        block_header.difficulty
+    };
+    ctx.machine_state.stack.push(value);
     Ok(None)
 }
 
@@ -54,7 +60,7 @@ pub fn gaslimit(ctx: &mut ExecutionContext) -> OpcodeResult {
 
 /// 0x46
 pub fn chain(ctx: &mut ExecutionContext) -> OpcodeResult {
-    let chain_id = U256::from(CHAIN_ID);
+    let chain_id = ctx.input.block_header.chain_id;
     ctx.machine_state.stack.push(chain_id);
     Ok(None)
 }
@@ -79,3 +85,26 @@ pub fn basefee(ctx: &mut ExecutionContext) -> OpcodeResult {
     ctx.machine_state.stack.push(base_fee);
     Ok(None)
 }
+
+/// 0x49
+/// Pushes the versioned hash of the blob at the given index,
+/// or zero if the transaction has not that many blobs
+pub fn blobhash(ctx: &mut ExecutionContext) -> OpcodeResult {
+    let index = pop_n(ctx, 1)?[0];
+
+    let blob_hash = usize::try_from(index)
+        .ok()
+        .and_then(|index| ctx.input.blob_hashes.get(index))
+        .copied()
+        .unwrap_or(U256::zero());
+    ctx.machine_state.stack.push(blob_hash);
+
+    Ok(None)
+}
+
+/// 0x4a
+pub fn blobbasefee(ctx: &mut ExecutionContext) -> OpcodeResult {
+    let blob_base_fee = ctx.input.block_header.blob_base_fee;
+    ctx.machine_state.stack.push(blob_base_fee);
+    Ok(None)
+}
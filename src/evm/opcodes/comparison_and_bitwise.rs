@@ -1,7 +1,6 @@
 use primitive_types::U256;
 
 use crate::evm::utils::{
-    errors::EVMError,
     helpers::{convert_twos_complement, is_negative, pop_n, swap_1},
     traits::Bool,
     types::{ExecutionContext, OpcodeResult}, constants::BYTES_IN_U256_FROM_ZERO,
@@ -108,15 +107,13 @@ pub fn not(ctx: &mut ExecutionContext) -> OpcodeResult {
 /// 0x1a
 pub fn byte(ctx: &mut ExecutionContext) -> OpcodeResult {
     let stack_items = pop_n(ctx, 2)?;
-    let index: usize = stack_items[0]
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(stack_items[0], ctx.clone()))?;
+    let index = stack_items[0];
     let value = stack_items[1];
 
-    let result = if index > BYTES_IN_U256_FROM_ZERO {
+    let result = if index > U256::from(BYTES_IN_U256_FROM_ZERO) {
         U256::zero()
     } else {
-        U256::from(value.byte(BYTES_IN_U256_FROM_ZERO - index))
+        U256::from(value.byte(BYTES_IN_U256_FROM_ZERO - index.as_usize()))
     };
 
     ctx.machine_state
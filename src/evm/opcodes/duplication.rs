@@ -11,7 +11,7 @@ pub fn dup(ctx: &mut ExecutionContext) -> OpcodeResult {
         .input
         .bytecode
         .get(ctx.machine_state.pc)
-        .ok_or(EVMError::NoBytecodeError(ctx.clone()))?;
+        .ok_or(EVMError::NoBytecodeError(ctx.machine_state.pc))?;
     let items_to_pop: usize = usize::from(1 + opcode - DUP_1);
     let stack_items = pop_n(ctx, items_to_pop)?;
     let item_to_dup = stack_items[items_to_pop - 1];
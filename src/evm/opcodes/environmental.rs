@@ -1,9 +1,11 @@
 use primitive_types::U256;
-use sha3::{Digest, Keccak256};
 
 use crate::evm::utils::{
     errors::EVMError,
-    helpers::{hex_string_from_bytes, modulo_address_size, pop_n, update_active_words_memory},
+    gas::{account_access_cost, charge_gas, words, G_COPY},
+    helpers::{
+        expand_memory, is_account_dead, memory_range, modulo_address_size, pop_n, read_padded,
+    },
     types::{ExecutionContext, OpcodeResult},
 };
 
@@ -16,6 +18,10 @@ pub fn address(ctx: &mut ExecutionContext) -> OpcodeResult {
 /// 0x31
 pub fn balance(ctx: &mut ExecutionContext) -> OpcodeResult {
     let address = modulo_address_size(&pop_n(ctx, 1)?[0]);
+
+    let access_cost = account_access_cost(ctx, address);
+    charge_gas(ctx, access_cost)?;
+
     let balance = if let Some(account_state) = ctx.global_state.get(&address) {
         account_state.balance
     } else {
@@ -23,7 +29,6 @@ pub fn balance(ctx: &mut ExecutionContext) -> OpcodeResult {
     };
 
     ctx.machine_state.stack.push(balance);
-    ctx.accrued_substate.accessed_accounts.insert(address);
 
     Ok(None)
 }
@@ -51,21 +56,9 @@ pub fn callvalue(ctx: &mut ExecutionContext) -> OpcodeResult {
 /// 0x35
 pub fn calldataload(ctx: &mut ExecutionContext) -> OpcodeResult {
     let offset = pop_n(ctx, 1)?[0];
-    let offset: usize = offset
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(offset, ctx.clone()))?;
-
-    let data: Vec<u8> = vec![0_u8; 32]
-        .iter()
-        .enumerate()
-        .map(|(idx, zero_byte)| *ctx.input.data.get(offset + idx).unwrap_or(zero_byte))
-        .collect();
 
-    let str_data = hex_string_from_bytes(&data);
-    let data = U256::from_str_radix(&str_data, 16)
-        .map_err(|_| EVMError::FromStrRadixError(str_data, ctx.clone()))?;
-
-    ctx.machine_state.stack.push(data);
+    let data = read_padded(&ctx.input.data, offset, 32);
+    ctx.machine_state.stack.push(U256::from_big_endian(&data));
 
     Ok(None)
 }
@@ -83,25 +76,14 @@ pub fn calldatasize(ctx: &mut ExecutionContext) -> OpcodeResult {
 pub fn calldatacopy(ctx: &mut ExecutionContext) -> OpcodeResult {
     let stack_items = pop_n(ctx, 3)?;
 
-    let dest_offset: usize = stack_items[0]
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(stack_items[0], ctx.clone()))?;
-    let offset: usize = stack_items[1]
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(stack_items[1], ctx.clone()))?;
-    let size: usize = stack_items[2]
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(stack_items[2], ctx.clone()))?;
+    let (dest_offset, size) = memory_range(ctx, stack_items[0], stack_items[2])?;
+    let offset = stack_items[1];
 
-    vec![0_u8; size]
-        .iter()
-        .enumerate()
-        .for_each(|(idx, zero_byte)| {
-            let byte = ctx.input.data.get(offset + idx).unwrap_or(zero_byte);
-            ctx.machine_state.memory[dest_offset + idx] = *byte
-        });
+    charge_gas(ctx, G_COPY * words(size))?;
+    expand_memory(ctx, dest_offset, size)?;
 
-    update_active_words_memory(ctx, offset + size);
+    let bytes = read_padded(&ctx.input.data, offset, size);
+    ctx.machine_state.memory[dest_offset..dest_offset + size].copy_from_slice(&bytes);
 
     Ok(None)
 }
@@ -117,25 +99,14 @@ pub fn codesize(ctx: &mut ExecutionContext) -> OpcodeResult {
 pub fn codecopy(ctx: &mut ExecutionContext) -> OpcodeResult {
     let stack_items = pop_n(ctx, 3)?;
 
-    let dest_offset: usize = stack_items[0]
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(stack_items[0], ctx.clone()))?;
-    let offset: usize = stack_items[1]
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(stack_items[1], ctx.clone()))?;
-    let size: usize = stack_items[2]
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(stack_items[2], ctx.clone()))?;
+    let (dest_offset, size) = memory_range(ctx, stack_items[0], stack_items[2])?;
+    let offset = stack_items[1];
 
-    vec![0_u8; size]
-        .iter()
-        .enumerate()
-        .for_each(|(idx, zero_byte)| {
-            let byte = ctx.input.bytecode.get(offset + idx).unwrap_or(zero_byte);
-            ctx.machine_state.memory[dest_offset + idx] = *byte
-        });
+    charge_gas(ctx, G_COPY * words(size))?;
+    expand_memory(ctx, dest_offset, size)?;
 
-    update_active_words_memory(ctx, offset + size);
+    let bytes = read_padded(&ctx.input.bytecode, offset, size);
+    ctx.machine_state.memory[dest_offset..dest_offset + size].copy_from_slice(&bytes);
 
     Ok(None)
 }
@@ -150,14 +121,12 @@ pub fn gasprice(ctx: &mut ExecutionContext) -> OpcodeResult {
 pub fn extcodesize(ctx: &mut ExecutionContext) -> OpcodeResult {
     let address = modulo_address_size(&pop_n(ctx, 1)?[0]);
 
-    let size = if let Some(account_state) = ctx.global_state.get(&address) {
-        U256::from(account_state.code.len())
-    } else {
-        U256::zero()
-    };
+    let access_cost = account_access_cost(ctx, address);
+    charge_gas(ctx, access_cost)?;
+
+    let size = U256::from(ctx.code(address).len());
 
     ctx.machine_state.stack.push(size);
-    ctx.accrued_substate.accessed_accounts.insert(address);
 
     Ok(None)
 }
@@ -167,33 +136,15 @@ pub fn extcodecopy(ctx: &mut ExecutionContext) -> OpcodeResult {
     let stack_items = pop_n(ctx, 4)?;
 
     let address = modulo_address_size(&stack_items[0]);
-    let dest_offset: usize = stack_items[1]
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(stack_items[1], ctx.clone()))?;
-    let offset: usize = stack_items[2]
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(stack_items[2], ctx.clone()))?;
-    let size: usize = stack_items[3]
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(stack_items[3], ctx.clone()))?;
-
-    let empty_code: Vec<u8> = Vec::with_capacity(0);
-    let code = if let Some(account_state) = ctx.global_state.get(&address) {
-        &account_state.code
-    } else {
-        &empty_code
-    };
+    let (dest_offset, size) = memory_range(ctx, stack_items[1], stack_items[3])?;
+    let offset = stack_items[2];
 
-    vec![0_u8; size]
-        .iter()
-        .enumerate()
-        .for_each(|(idx, zero_byte)| {
-            let byte = code.get(offset + idx).unwrap_or(zero_byte);
-            ctx.machine_state.memory[dest_offset + idx] = *byte
-        });
+    let access_cost = account_access_cost(ctx, address);
+    charge_gas(ctx, access_cost + G_COPY * words(size))?;
+    expand_memory(ctx, dest_offset, size)?;
 
-    update_active_words_memory(ctx, offset + size);
-    ctx.accrued_substate.accessed_accounts.insert(address);
+    let bytes = read_padded(&ctx.code(address), offset, size);
+    ctx.machine_state.memory[dest_offset..dest_offset + size].copy_from_slice(&bytes);
 
     Ok(None)
 }
@@ -208,21 +159,20 @@ pub fn returndatasize(ctx: &mut ExecutionContext) -> OpcodeResult {
 /// 0x3e
 pub fn returndatacopy(ctx: &mut ExecutionContext) -> OpcodeResult {
     let stack_items = pop_n(ctx, 3)?;
-    let dest_offset: usize = stack_items[0]
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(stack_items[0], ctx.clone()))?;
-    let offset: usize = stack_items[1]
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(stack_items[1], ctx.clone()))?;
-    let size: usize = stack_items[2]
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(stack_items[2], ctx.clone()))?;
-
-    for i in offset..offset + size {
-        ctx.machine_state.memory[dest_offset + i] = *ctx.machine_state.output.get(i).unwrap_or(&0);
+    let (dest_offset, size) = memory_range(ctx, stack_items[0], stack_items[2])?;
+    let offset = stack_items[1];
+
+    // reading past the end of the return data is an exceptional halt (EIP-211)
+    let (end, overflow) = offset.overflowing_add(stack_items[2]);
+    if overflow || end > U256::from(ctx.machine_state.output.len()) {
+        return Err(EVMError::ReturnDataOutOfBoundsError(ctx.machine_state.pc));
     }
 
-    update_active_words_memory(ctx, dest_offset + size);
+    charge_gas(ctx, G_COPY * words(size))?;
+    expand_memory(ctx, dest_offset, size)?;
+
+    let bytes = read_padded(&ctx.machine_state.output, offset, size);
+    ctx.machine_state.memory[dest_offset..dest_offset + size].copy_from_slice(&bytes);
 
     Ok(None)
 }
@@ -231,20 +181,16 @@ pub fn returndatacopy(ctx: &mut ExecutionContext) -> OpcodeResult {
 pub fn extcodehash(ctx: &mut ExecutionContext) -> OpcodeResult {
     let address = modulo_address_size(&pop_n(ctx, 1)?[0]);
 
-    if let Some(account_state) = ctx.global_state.get(&address) {
-        let mut hasher = Keccak256::new();
-        hasher.update(&account_state.code);
-
-        let hash = hasher.finalize();
-        let hash_vec = hash.to_vec();
+    let access_cost = account_access_cost(ctx, address);
+    charge_gas(ctx, access_cost)?;
 
-        let val = U256::from(&hash_vec[..]);
-        ctx.machine_state.stack.push(val);
+    // the hash is zero for non-existent and empty accounts (EIP-1052)
+    let hash = if is_account_dead(&ctx.global_state, address) {
+        U256::zero()
     } else {
-        ctx.machine_state.stack.push(U256::zero())
-    }
-
-    ctx.accrued_substate.accessed_accounts.insert(address);
+        ctx.global_state[&address].code_hash
+    };
+    ctx.machine_state.stack.push(hash);
 
     Ok(None)
 }
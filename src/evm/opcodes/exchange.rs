@@ -11,7 +11,7 @@ pub fn swap(ctx: &mut ExecutionContext) -> OpcodeResult {
         .input
         .bytecode
         .get(ctx.machine_state.pc)
-        .ok_or(EVMError::NoBytecodeError(ctx.clone()))?;
+        .ok_or(EVMError::NoBytecodeError(ctx.machine_state.pc))?;
 
     let n: usize = (opcode - SWAP_1 + 1).into();
 
@@ -1,7 +1,8 @@
 use crate::evm::utils::{
     constants::LOG_0,
     errors::EVMError,
-    helpers::{pop_n, update_active_words_memory},
+    gas::{charge_gas, G_LOGDATA, G_LOGTOPIC},
+    helpers::{expand_memory, memory_range, pop_n},
     types::{ExecutionContext, Log, OpcodeResult},
 };
 
@@ -11,33 +12,25 @@ pub fn log(ctx: &mut ExecutionContext) -> OpcodeResult {
         .input
         .bytecode
         .get(ctx.machine_state.pc)
-        .ok_or(EVMError::NoBytecodeError(ctx.clone()))?;
+        .ok_or(EVMError::NoBytecodeError(ctx.machine_state.pc))?;
 
     let n = opcode - LOG_0;
 
     let stack_items = pop_n(ctx, 2 + usize::from(n))?;
-    let offset: usize = stack_items[0]
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(stack_items[0], ctx.clone()))?;
-    let size: usize = stack_items[1]
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(stack_items[1], ctx.clone()))?;
+    let (offset, size) = memory_range(ctx, stack_items[0], stack_items[1])?;
 
-    let mut data: Vec<u8> = Vec::with_capacity(size);
-    for i in offset..offset + size {
-        data.push(*ctx.machine_state.memory.get(i).unwrap_or(&0))
-    }
+    charge_gas(
+        ctx,
+        G_LOGDATA.saturating_mul(size as u64) + G_LOGTOPIC * u64::from(n),
+    )?;
+    expand_memory(ctx, offset, size)?;
 
-    update_active_words_memory(ctx, offset + size);
+    let data = ctx.machine_state.memory[offset..offset + size].to_vec();
 
-    ctx.accrued_substate.logs.push(Log {
+    ctx.add_log(Log {
         address: ctx.input.address,
         data,
-        topics: if n > 0 {
-            stack_items[3..].to_vec()
-        } else {
-            Vec::new()
-        },
+        topics: stack_items[2..].to_vec(),
     });
 
     Ok(None)
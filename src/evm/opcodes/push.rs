@@ -13,7 +13,7 @@ pub fn push(ctx: &mut ExecutionContext) -> OpcodeResult {
     let bytecode = &ctx.input.bytecode;
     let opcode = bytecode
         .get(pc)
-        .ok_or(EVMError::NoBytecodeError(ctx.clone()))?
+        .ok_or(EVMError::NoBytecodeError(ctx.machine_state.pc))?
         .clone();
 
     if opcode == PUSH_0 {
@@ -26,14 +26,14 @@ pub fn push(ctx: &mut ExecutionContext) -> OpcodeResult {
 
     let data = bytecode
         .get(data_position_hex..data_position_hex + offset)
-        .ok_or(EVMError::NoBytecodeError(ctx.clone()))?;
+        .ok_or(EVMError::NoBytecodeError(ctx.machine_state.pc))?;
 
     ctx.machine_state.pc += data.len();
 
     let str_data = hex_string_from_bytes(data);
 
     let data = U256::from_str_radix(&str_data, 16)
-        .map_err(|_err| EVMError::FromStrRadixError(str_data, ctx.clone()))?;
+        .map_err(|_err| EVMError::FromStrRadixError(str_data))?;
 
     ctx.machine_state.stack.push(data);
 
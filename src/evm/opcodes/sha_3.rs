@@ -2,8 +2,8 @@ use primitive_types::U256;
 use sha3::{Digest, Keccak256};
 
 use crate::evm::utils::{
-    errors::EVMError,
-    helpers::{pop_n, update_active_words_memory},
+    gas::{charge_gas, words, G_KECCAK256WORD},
+    helpers::{expand_memory, memory_range, pop_n},
     types::{ExecutionContext, OpcodeResult},
 };
 
@@ -12,15 +12,12 @@ pub fn sha3(ctx: &mut ExecutionContext) -> OpcodeResult {
 
     let stack_items = pop_n(ctx, 2)?;
 
-    let starting_offset: usize = stack_items[0]
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(stack_items[0], ctx.clone()))?;
-    let ending_offset: usize = stack_items[1]
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(stack_items[1], ctx.clone()))?;
+    let (offset, size) = memory_range(ctx, stack_items[0], stack_items[1])?;
 
-    let data = &ctx.machine_state.memory[starting_offset..ending_offset];
-    hasher.update(data);
+    charge_gas(ctx, G_KECCAK256WORD * words(size))?;
+    expand_memory(ctx, offset, size)?;
+
+    hasher.update(&ctx.machine_state.memory[offset..offset + size]);
 
     let hash = hasher.finalize();
     let hash_vec = hash.to_vec();
@@ -28,7 +25,5 @@ pub fn sha3(ctx: &mut ExecutionContext) -> OpcodeResult {
     let val = U256::from(&hash_vec[..]);
     ctx.machine_state.stack.push(val);
 
-    update_active_words_memory(ctx, ending_offset);
-
     Ok(None)
 }
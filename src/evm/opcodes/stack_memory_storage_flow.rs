@@ -3,17 +3,22 @@ use primitive_types::U256;
 use crate::evm::utils::{
     constants::BYTES_IN_U256_FROM_ZERO,
     errors::EVMError,
-    helpers::{get_jumpdests, hex_string_from_byte, pop_n, update_active_words_memory},
-    types::{AccountState, ExecutionContext, OpcodeResult},
+    gas::{
+        charge_gas, storage_access_cost, words, G_CALLSTIPEND, G_COLDSLOAD, G_COPY,
+        G_SLOAD_ISTANBUL, G_SRESET, G_SRESET_FRONTIER, G_SSET, G_WARMACCESS, R_SCLEAR,
+        R_SCLEAR_FRONTIER,
+    },
+    helpers::{expand_memory, get_jumpdests, hex_string_from_byte, memory_range, pop_n},
+    spec::SpecId,
+    types::{ExecutionContext, OpcodeResult},
 };
 
 /// 0x50
 pub fn pop(ctx: &mut ExecutionContext) -> OpcodeResult {
-    let _ = ctx
-        .machine_state
+    ctx.machine_state
         .stack
         .pop()
-        .ok_or(EVMError::EmptyStackError(ctx.clone()));
+        .ok_or(EVMError::EmptyStackError)?;
 
     Ok(None)
 }
@@ -22,9 +27,8 @@ pub fn pop(ctx: &mut ExecutionContext) -> OpcodeResult {
 pub fn mload(ctx: &mut ExecutionContext) -> OpcodeResult {
     let offset = pop_n(ctx, 1)?[0];
 
-    let offset: usize = offset
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(offset, ctx.clone()))?;
+    let (offset, _) = memory_range(ctx, offset, U256::from(32))?;
+    expand_memory(ctx, offset, 32)?;
 
     let mut value_str = String::with_capacity(64);
 
@@ -34,13 +38,11 @@ pub fn mload(ctx: &mut ExecutionContext) -> OpcodeResult {
         value_str.push_str(&hex_string_from_byte(*byte))
     }
 
-    let value = U256::from_str_radix(&value_str, 16)
-        .map_err(|_| EVMError::FromStrRadixError(value_str, ctx.clone()))?;
+    let value =
+        U256::from_str_radix(&value_str, 16).map_err(|_| EVMError::FromStrRadixError(value_str))?;
 
     ctx.machine_state.stack.push(value);
 
-    update_active_words_memory(ctx, offset + BYTES_IN_U256_FROM_ZERO);
-
     Ok(None)
 }
 
@@ -49,23 +51,17 @@ pub fn mstore(ctx: &mut ExecutionContext) -> OpcodeResult {
     let stack_items = pop_n(ctx, 2)?;
 
     let offset = stack_items[0];
-    let offset: usize = offset
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(offset, ctx.clone()))?;
+    let (offset, _) = memory_range(ctx, offset, U256::from(32))?;
 
     let value = stack_items[1];
 
+    expand_memory(ctx, offset, 32)?;
+
     for i in 0..=BYTES_IN_U256_FROM_ZERO {
         let byte = value.byte(BYTES_IN_U256_FROM_ZERO - i);
-        if offset + i < ctx.machine_state.memory.len() {
-            ctx.machine_state.memory[offset + i] = byte;
-        } else {
-            ctx.machine_state.memory.push(byte);
-        }
+        ctx.machine_state.memory[offset + i] = byte;
     }
 
-    update_active_words_memory(ctx, offset + BYTES_IN_U256_FROM_ZERO);
-
     Ok(None)
 }
 
@@ -74,18 +70,14 @@ pub fn mstore8(ctx: &mut ExecutionContext) -> OpcodeResult {
     let stack_items = pop_n(ctx, 2)?;
 
     let offset = stack_items[0];
-    let offset: usize = offset
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(offset, ctx.clone()))?;
+    let (offset, _) = memory_range(ctx, offset, U256::one())?;
 
-    let value = stack_items[1];
-    let value: u8 = value
-        .try_into()
-        .map_err(|_| EVMError::U256ToU8Error(value, ctx.clone()))?;
+    // only the least significant byte is stored
+    let value = stack_items[1].byte(0);
 
-    ctx.machine_state.memory[offset] = value;
+    expand_memory(ctx, offset, 1)?;
 
-    update_active_words_memory(ctx, offset);
+    ctx.machine_state.memory[offset] = value;
 
     Ok(None)
 }
@@ -95,7 +87,10 @@ pub fn sload(ctx: &mut ExecutionContext) -> OpcodeResult {
     let key = pop_n(ctx, 1)?[0];
 
     let address = ctx.input.address;
-    let value = if let Some(account_state) = ctx.global_state.get_mut(&address) {
+    let access_cost = storage_access_cost(ctx, address, key);
+    charge_gas(ctx, access_cost)?;
+
+    let value = if let Some(account_state) = ctx.global_state.get(&address) {
         account_state
             .storage
             .get(&key)
@@ -106,32 +101,98 @@ pub fn sload(ctx: &mut ExecutionContext) -> OpcodeResult {
     };
 
     ctx.machine_state.stack.push(value);
-    ctx.accrued_substate
-        .accessed_storage_keys
-        .insert((address, key));
 
     Ok(None)
 }
 
 /// 0x55
+/// Before Istanbul, the cost only depends on the current and the new value.
+/// From Istanbul, gas and refunds follow the net gas metering rules of
+/// EIP-2200, with the constants updated by EIP-2929 and EIP-3529
 pub fn sstore(ctx: &mut ExecutionContext) -> OpcodeResult {
     let address = ctx.input.address;
+    let spec = ctx.spec;
     let stack_items = pop_n(ctx, 2)?;
 
     let key = stack_items[0];
     let value = stack_items[1];
 
-    if let Some(account_state) = ctx.global_state.get_mut(&address) {
-        account_state.storage.insert(key, value);
+    // a call with the stipend only cannot modify the storage
+    if spec.is_enabled(SpecId::Istanbul) && ctx.machine_state.gas <= U256::from(G_CALLSTIPEND) {
+        return Err(EVMError::OutOfGasError(ctx.machine_state.pc));
+    }
+
+    // accessing a cold slot costs extra, on top of the cost of the write
+    if storage_access_cost(ctx, address, key) == G_COLDSLOAD {
+        charge_gas(ctx, G_COLDSLOAD)?;
+    }
+
+    let current_value = ctx
+        .global_state
+        .get(&address)
+        .and_then(|account_state| account_state.storage.get(&key).copied())
+        .unwrap_or(U256::zero());
+    let original_value = *ctx
+        .original_storage
+        .entry((address, key))
+        .or_insert(current_value);
+
+    let clear_refund = U256::from(if spec.is_enabled(SpecId::London) {
+        R_SCLEAR
+    } else {
+        R_SCLEAR_FRONTIER
+    });
+    let (sload_cost, reset_cost) = if spec.is_enabled(SpecId::Berlin) {
+        (G_WARMACCESS, G_SRESET)
+    } else {
+        (G_SLOAD_ISTANBUL, G_SRESET_FRONTIER)
+    };
+
+    let refund = ctx.refund_balance_mut();
+    let cost = if !spec.is_enabled(SpecId::Istanbul) {
+        if current_value.is_zero() && !value.is_zero() {
+            G_SSET
+        } else {
+            if !current_value.is_zero() && value.is_zero() {
+                *refund += clear_refund;
+            }
+            G_SRESET_FRONTIER
+        }
+    } else if current_value == value {
+        // no-op
+        sload_cost
+    } else if original_value == current_value {
+        // clean slot
+        if original_value.is_zero() {
+            G_SSET
+        } else {
+            if value.is_zero() {
+                *refund += clear_refund;
+            }
+            reset_cost
+        }
     } else {
-        let mut account_state = AccountState::new();
-        account_state.storage.insert(key, value);
-        ctx.global_state.insert(address, account_state);
+        // dirty slot
+        if !original_value.is_zero() {
+            if current_value.is_zero() {
+                *refund = refund.saturating_sub(clear_refund);
+            } else if value.is_zero() {
+                *refund += clear_refund;
+            }
+        }
+        if original_value == value {
+            // the slot is reset to its original value
+            if original_value.is_zero() {
+                *refund += U256::from(G_SSET - sload_cost);
+            } else {
+                *refund += U256::from(reset_cost - sload_cost);
+            }
+        }
+        sload_cost
     };
+    charge_gas(ctx, cost)?;
 
-    ctx.accrued_substate
-        .accessed_storage_keys
-        .insert((address, key));
+    ctx.set_storage(address, key, value);
 
     Ok(None)
 }
@@ -141,7 +202,7 @@ pub fn jump(ctx: &mut ExecutionContext) -> OpcodeResult {
     let counter = pop_n(ctx, 1)?[0];
     let counter: usize = counter
         .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(counter, ctx.clone()))?;
+        .map_err(|_| EVMError::U256ToUSizeError(counter))?;
 
     let jumpdests = get_jumpdests(ctx);
 
@@ -149,11 +210,11 @@ pub fn jump(ctx: &mut ExecutionContext) -> OpcodeResult {
         ctx.input
             .bytecode
             .get(counter)
-            .ok_or(EVMError::NoBytecodeError(ctx.clone()))?,
+            .ok_or(EVMError::NoBytecodeError(ctx.machine_state.pc))?,
     );
 
     if !can_jump {
-        return Err(EVMError::InvalidJumpdestError(counter, ctx.clone()));
+        return Err(EVMError::InvalidJumpdestError(counter));
     }
 
     ctx.machine_state.pc = counter;
@@ -189,9 +250,9 @@ pub fn msize(ctx: &mut ExecutionContext) -> OpcodeResult {
     Ok(None)
 }
 
-/// 0x5a not implemented
+/// 0x5a
 pub fn gas(ctx: &mut ExecutionContext) -> OpcodeResult {
-    ctx.machine_state.stack.push(U256::MAX);
+    ctx.machine_state.stack.push(ctx.machine_state.gas);
     Ok(None)
 }
 
@@ -199,3 +260,51 @@ pub fn gas(ctx: &mut ExecutionContext) -> OpcodeResult {
 pub fn jumpdest(_ctx: &mut ExecutionContext) -> OpcodeResult {
     Ok(None)
 }
+
+/// 0x5c
+pub fn tload(ctx: &mut ExecutionContext) -> OpcodeResult {
+    let key = pop_n(ctx, 1)?[0];
+
+    let value = ctx
+        .accrued_substate
+        .transient_storage
+        .get(&(ctx.input.address, key))
+        .copied()
+        .unwrap_or(U256::zero());
+    ctx.machine_state.stack.push(value);
+
+    Ok(None)
+}
+
+/// 0x5d
+pub fn tstore(ctx: &mut ExecutionContext) -> OpcodeResult {
+    let stack_items = pop_n(ctx, 2)?;
+
+    let key = stack_items[0];
+    let value = stack_items[1];
+
+    ctx.set_transient_storage(ctx.input.address, key, value);
+
+    Ok(None)
+}
+
+/// 0x5e
+/// The source and the destination may overlap, in which case
+/// the copy behaves as if an intermediate buffer was used
+pub fn mcopy(ctx: &mut ExecutionContext) -> OpcodeResult {
+    let stack_items = pop_n(ctx, 3)?;
+
+    let (dest_offset, size) = memory_range(ctx, stack_items[0], stack_items[2])?;
+    let (offset, _) = memory_range(ctx, stack_items[1], stack_items[2])?;
+
+    charge_gas(ctx, G_COPY * words(size))?;
+    expand_memory(ctx, offset.max(dest_offset), size)?;
+
+    if size > 0 {
+        ctx.machine_state
+            .memory
+            .copy_within(offset..offset + size, dest_offset);
+    }
+
+    Ok(None)
+}
@@ -1,8 +1,9 @@
 use primitive_types::U256;
 
 use crate::evm::utils::{
-    errors::EVMError,
+    gas::{charge_gas, G_EXPBYTE, G_EXPBYTE_FRONTIER},
     helpers::{convert_twos_complement, is_negative, pop_n},
+    spec::SpecId,
     types::{ExecutionContext, OpcodeResult},
 };
 
@@ -157,6 +158,14 @@ pub fn mulmod(ctx: &mut ExecutionContext) -> OpcodeResult {
 pub fn exp(ctx: &mut ExecutionContext) -> OpcodeResult {
     let stack_items = pop_n(ctx, 2)?;
 
+    let exponent_bytes = (stack_items[1].bits() as u64).div_ceil(8);
+    let byte_cost = if ctx.spec.is_enabled(SpecId::SpuriousDragon) {
+        G_EXPBYTE
+    } else {
+        G_EXPBYTE_FRONTIER
+    };
+    charge_gas(ctx, byte_cost * exponent_bytes)?;
+
     let result = stack_items[0].overflowing_pow(stack_items[1]).0;
     ctx.machine_state.stack.push(result);
 
@@ -167,16 +176,14 @@ pub fn exp(ctx: &mut ExecutionContext) -> OpcodeResult {
 pub fn signextend(ctx: &mut ExecutionContext) -> OpcodeResult {
     let stack_items = pop_n(ctx, 2)?;
 
-    let size_in_bytes_minus_one: u8 = stack_items[0]
-        .try_into()
-        .map_err(|_| EVMError::U256ToU8Error(stack_items[0], ctx.clone()))?;
     let int_to_extend = stack_items[1];
 
-    if size_in_bytes_minus_one >= 32 {
+    if stack_items[0] >= U256::from(32) {
         // cannot extend more
         ctx.machine_state.stack.push(int_to_extend);
         return Ok(None);
     }
+    let size_in_bytes_minus_one = stack_items[0].as_u32();
 
     let bit_index = (8 * size_in_bytes_minus_one + 7) as usize;
     // find whether the bit at bit_index is 1 or 0
@@ -1,119 +1,135 @@
-use std::collections::HashMap;
-
 use primitive_types::U256;
-use sha3::{Digest, Keccak256};
 
 use crate::evm::{
     evm::EVM,
     utils::{
-        constants::KECCAK_EMPTY,
+        constants::{CALL_DEPTH_LIMIT, MAX_INITCODE_SIZE},
         errors::EVMError,
-        helpers::{modulo_address_size, pop_n, update_active_words_memory},
-        types::{AccountState, ExecutionContext, MachineState, OpcodeResult},
+        gas::{
+            account_access_cost, all_but_one_64th, charge_gas, words, G_CALLSTIPEND, G_CALLVALUE,
+            G_COLDACCOUNTACCESS, G_INITCODEWORD, G_KECCAK256WORD, G_NEWACCOUNT,
+            R_SELFDESTRUCT_FRONTIER,
+        },
+        helpers::{
+            create2_address, create_address, expand_memory, is_account_dead, memory_range,
+            modulo_address_size, pop_n, transfer,
+        },
+        spec::SpecId,
+        types::{ExecutionContext, MachineState, OpcodeResult},
     },
 };
 
-use super::environmental::balance;
-
 /// 0xf0
 pub fn create(ctx: &mut ExecutionContext) -> OpcodeResult {
     let stack_items = pop_n(ctx, 3)?;
     let value = stack_items[0];
-    let offset: usize = stack_items[1]
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(stack_items[1], ctx.clone()))?;
-    let size: usize = stack_items[2]
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(stack_items[2], ctx.clone()))?;
-
-    let sender = ctx.input.sender;
-    let (nonce, _balance) = if let Some(account_state) = ctx.global_state.get_mut(&sender) {
-        (account_state.nonce, account_state.balance)
-    } else {
-        let new_account_state = AccountState::new();
-        let nonce = new_account_state.nonce;
-        let balance = new_account_state.balance;
-        ctx.global_state.insert(sender, new_account_state);
-        (nonce, balance)
-    };
+    let (offset, size) = memory_range(ctx, stack_items[1], stack_items[2])?;
+
+    expand_memory(ctx, offset, size)?;
+
+    let sender = ctx.input.address;
+    let nonce = ctx
+        .global_state
+        .get(&sender)
+        .map(|account_state| account_state.nonce)
+        .unwrap_or(0);
+
+    let initialisation_code = ctx.machine_state.memory[offset..offset + size].to_vec();
+    let new_account_address = create_address(sender, nonce);
+
+    create_contract(ctx, value, initialisation_code, new_account_address)
+}
+
+/// 0xf5
+pub fn create2(ctx: &mut ExecutionContext) -> OpcodeResult {
+    let stack_items = pop_n(ctx, 4)?;
+    let value = stack_items[0];
+    let (offset, size) = memory_range(ctx, stack_items[1], stack_items[2])?;
+    let salt = stack_items[3];
+
+    // the initialisation code has to be hashed to get the address
+    charge_gas(ctx, G_KECCAK256WORD * words(size))?;
+    expand_memory(ctx, offset, size)?;
 
-    /*
-     * tests do not handle this case
-    if value > balance {
+    let initialisation_code = ctx.machine_state.memory[offset..offset + size].to_vec();
+    let new_account_address = create2_address(ctx.input.address, salt, &initialisation_code);
+
+    create_contract(ctx, value, initialisation_code, new_account_address)
+}
+
+/// Creates a new account at `new_account_address`, endowed with `value`,
+/// whose code is the output of `initialisation_code`.
+/// This is the part of the contract creation shared by CREATE and CREATE2
+fn create_contract(
+    ctx: &mut ExecutionContext,
+    value: U256,
+    initialisation_code: Vec<u8>,
+    new_account_address: U256,
+) -> OpcodeResult {
+    // the initialisation code is limited in size and priced
+    // per word from Shanghai (EIP-3860)
+    if ctx.spec.is_enabled(SpecId::Shanghai) {
+        if initialisation_code.len() > MAX_INITCODE_SIZE {
+            return Err(EVMError::InitcodeSizeError(initialisation_code.len()));
+        }
+        charge_gas(ctx, G_INITCODEWORD * words(initialisation_code.len()))?;
+    }
+
+    let sender = ctx.input.address;
+    let balance = ctx
+        .global_state
+        .get(&sender)
+        .map(|account_state| account_state.balance)
+        .unwrap_or(U256::zero());
+
+    if value > balance || ctx.input.depth == CALL_DEPTH_LIMIT {
+        ctx.machine_state.output = Vec::new();
+        ctx.machine_state.stack.push(U256::zero());
         return Ok(None);
     };
-    */
-
-    let mut hasher = Keccak256::new();
-    let to_hash = [
-        (0..32)
-            .into_iter()
-            .map(|i| sender.byte(i))
-            .collect::<Vec<u8>>(),
-        nonce.to_le_bytes().to_vec(),
-    ]
-    .concat(); // no rlp here, overkill
-    hasher.update(to_hash);
-
-    let new_account_address =
-        U256::from(hasher.finalize().to_vec().get(12..).unwrap_or(&[0_u8; 20]));
-
-    let initialisation_code = &ctx.machine_state.memory[offset..offset + size].to_owned();
-
-    ctx.global_state.insert(
-        new_account_address,
-        AccountState {
-            nonce: 0,
-            balance: value,
-            code_hash: KECCAK_EMPTY,
-            code: initialisation_code.to_vec(),
-            storage_root: KECCAK_EMPTY,
-            storage: HashMap::new(),
-        },
-    );
+
+    // the nonce is incremented even if the creation fails from now on
+    ctx.account_mut(sender).nonce += 1;
+
+    // the address of the new account is warm from now on (EIP-2929)
+    ctx.warm_account(new_account_address);
+
+    // before Tangerine Whistle, all the gas is given
+    let callee_gas = if ctx.spec.is_enabled(SpecId::TangerineWhistle) {
+        all_but_one_64th(ctx.machine_state.gas)
+    } else {
+        ctx.machine_state.gas
+    };
+    charge_gas(ctx, callee_gas)?;
 
     let old_input = ctx.input.clone();
     let old_machine_state = ctx.machine_state.clone();
 
     // prepare sub-context
     ctx.machine_state = MachineState::new();
+    ctx.machine_state.gas = callee_gas;
     ctx.input.sender = ctx.input.address;
-    ctx.input.bytecode = initialisation_code.clone();
+    ctx.input.value = value;
+    ctx.input.data = Vec::new();
+    ctx.input.bytecode = initialisation_code.into();
     ctx.input.address = new_account_address;
     ctx.input.depth += 1;
 
-    let result = EVM::execute(ctx)?;
-
-    let output = result.output.unwrap_or(Vec::new());
+    let result = EVM::execute_create(ctx);
 
     // restore context
     ctx.input = old_input;
     ctx.machine_state = old_machine_state;
-    ctx.machine_state.output = output.clone();
+    ctx.machine_state.gas += callee_gas - result.gas_used;
 
     // handle result
+    ctx.machine_state.output = result.output.unwrap_or_default();
     if result.success {
-        // the new account's code is set to the return data
-        let account_state = ctx.global_state.get_mut(&new_account_address).unwrap();
-        let code = output;
-        let mut hasher = Keccak256::new();
-        hasher.update(initialisation_code);
-        let code_hash = if code.len() > 0 {
-            U256::from(hasher.finalize().as_slice())
-        } else {
-            KECCAK_EMPTY
-        };
-
-        account_state.code = code;
-        account_state.code_hash = code_hash;
-
         ctx.machine_state.stack.push(new_account_address);
     } else {
         ctx.machine_state.stack.push(U256::zero());
     }
 
-    update_active_words_memory(ctx, offset + size);
-
     Ok(None)
 }
 
@@ -121,192 +137,254 @@ pub fn create(ctx: &mut ExecutionContext) -> OpcodeResult {
 pub fn call(ctx: &mut ExecutionContext) -> OpcodeResult {
     let stack_items = pop_n(ctx, 7)?;
 
-    let _gas = stack_items[0];
-    let address = stack_items[1];
+    let address = modulo_address_size(&stack_items[1]);
     let value = stack_items[2];
-    let args_offset: usize = stack_items[3]
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(stack_items[3], ctx.clone()))?;
-    let args_size: usize = stack_items[4]
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(stack_items[4], ctx.clone()))?;
-    let ret_offset: usize = stack_items[5]
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(stack_items[5], ctx.clone()))?;
-    let ret_size: usize = stack_items[6]
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(stack_items[6], ctx.clone()))?;
-
-    let insufficient_balance = ctx
-        .global_state
-        .get(&ctx.input.address)
-        .unwrap_or(&AccountState::new()) // maybe an error for this?
-        .balance
-        < value;
-    let call_depth_limit_reached = ctx.input.depth == 1024;
 
-    if insufficient_balance || call_depth_limit_reached {
-        ctx.machine_state.stack.push(U256::zero());
-        return Ok(None);
-    }
-
-    // prepare call
-    let mut calldata: Vec<u8> = Vec::new();
-    for i in args_offset..args_offset + args_size {
-        calldata.push(*ctx.machine_state.memory.get(i).unwrap_or(&0))
+    // transferring value is a modification of the state
+    if !value.is_zero() && !ctx.input.write {
+        return Err(EVMError::WriteProtectionError(ctx.machine_state.pc));
     }
 
-    update_active_words_memory(ctx, args_offset + args_size);
-
-    let old_input = ctx.input.clone();
-    let old_machine_state = ctx.machine_state.clone();
-
-    ctx.input.sender = old_input.address;
-    ctx.input.address = modulo_address_size(&address);
-    ctx.input.value = value;
-    ctx.input.bytecode = if let Some(account_state) = ctx.global_state.get(&address) {
-        account_state.code.clone()
-    } else {
-        Vec::new()
-    };
-    ctx.input.depth += 1;
-    ctx.machine_state = MachineState::new();
-
-    // call
-    let result = EVM::execute(ctx)?;
-
-    // restore current ctx and manage result
-    ctx.input = old_input;
-    ctx.machine_state = old_machine_state;
-    ctx.machine_state.output = result.output.unwrap_or(Vec::new());
-    ctx.machine_state.stack.push(U256::from(if result.success {
-        U256::one()
-    } else {
-        U256::zero()
-    }));
-    ctx.accrued_substate.accessed_accounts.insert(address);
+    message_call(
+        ctx,
+        MessageCall {
+            gas: stack_items[0],
+            code_address: address,
+            recipient: address,
+            sender: ctx.input.address,
+            value,
+            apparent_value: value,
+            args_offset: stack_items[3],
+            args_size: stack_items[4],
+            ret_offset: stack_items[5],
+            ret_size: stack_items[6],
+            write: ctx.input.write,
+        },
+    )
+}
 
-    let return_data_length = ret_size.min(ctx.machine_state.output.len());
+/// 0xf2
+/// Runs the code of another account in the current one, which
+/// sends `value` to itself
+pub fn callcode(ctx: &mut ExecutionContext) -> OpcodeResult {
+    let stack_items = pop_n(ctx, 7)?;
 
-    for i in 0..return_data_length {
-        ctx.machine_state.memory[ret_offset + i] = *ctx.machine_state.output.get(i).unwrap_or(&0);
-    }
-    update_active_words_memory(ctx, ret_offset + ret_size);
+    let value = stack_items[2];
 
-    Ok(None)
+    message_call(
+        ctx,
+        MessageCall {
+            gas: stack_items[0],
+            code_address: modulo_address_size(&stack_items[1]),
+            recipient: ctx.input.address,
+            sender: ctx.input.address,
+            value,
+            apparent_value: value,
+            args_offset: stack_items[3],
+            args_size: stack_items[4],
+            ret_offset: stack_items[5],
+            ret_size: stack_items[6],
+            write: ctx.input.write,
+        },
+    )
 }
 
 /// 0xf3
 pub fn r#return(ctx: &mut ExecutionContext) -> OpcodeResult {
     let stack_items = pop_n(ctx, 2)?;
 
-    let offset: usize = stack_items[0]
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(stack_items[0], ctx.clone()))?;
-    let size: usize = stack_items[1]
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(stack_items[1], ctx.clone()))?;
+    let (offset, size) = memory_range(ctx, stack_items[0], stack_items[1])?;
 
-    let mut data: Vec<u8> = Vec::with_capacity(size);
-    for i in offset..offset + size {
-        data.push(*ctx.machine_state.memory.get(i).unwrap_or(&0))
-    }
+    expand_memory(ctx, offset, size)?;
 
-    update_active_words_memory(ctx, offset + size);
+    let data = ctx.machine_state.memory[offset..offset + size].to_vec();
 
     Ok(Some(data))
 }
 
 /// 0xf4
+/// Runs the code of another account in the current one, keeping
+/// the sender and the value of the current context
 pub fn delegatecall(ctx: &mut ExecutionContext) -> OpcodeResult {
     let stack_items = pop_n(ctx, 6)?;
 
-    let _gas = stack_items[0];
-    let address = stack_items[1];
-    let args_offset: usize = stack_items[2]
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(stack_items[2], ctx.clone()))?;
-    let args_size: usize = stack_items[3]
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(stack_items[3], ctx.clone()))?;
-    let ret_offset: usize = stack_items[4]
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(stack_items[4], ctx.clone()))?;
-    let ret_size: usize = stack_items[5]
-        .try_into()
-        .map_err(|_| EVMError::U256ToUSizeError(stack_items[5], ctx.clone()))?;
+    message_call(
+        ctx,
+        MessageCall {
+            gas: stack_items[0],
+            code_address: modulo_address_size(&stack_items[1]),
+            recipient: ctx.input.address,
+            sender: ctx.input.sender,
+            value: U256::zero(),
+            apparent_value: ctx.input.value,
+            args_offset: stack_items[2],
+            args_size: stack_items[3],
+            ret_offset: stack_items[4],
+            ret_size: stack_items[5],
+            write: ctx.input.write,
+        },
+    )
+}
+
+/// The parameters of a message call, named after the ones of the
+/// message call function Θ in the yellow paper
+struct MessageCall {
+    gas: U256,
+
+    /// the account whose code is executed
+    code_address: U256,
+
+    /// the account whose storage is used by the callee, which
+    /// receives `value`
+    recipient: U256,
+
+    /// the sender seen by the callee
+    sender: U256,
+
+    /// the value transferred from the current account to the recipient
+    value: U256,
+
+    /// the value seen by the callee, which differs from the
+    /// transferred one for DELEGATECALL
+    apparent_value: U256,
+
+    args_offset: U256,
+    args_size: U256,
+    ret_offset: U256,
+    ret_size: U256,
+
+    /// the permission of the callee to modify the state, which is
+    /// never given back once it is revoked by STATICCALL
+    write: bool,
+}
+
+/// Executes a message call in a sub-context, then copies its return
+/// data to memory and pushes whether it succeeded.
+/// This is the part shared by the CALL family of opcodes
+fn message_call(ctx: &mut ExecutionContext, call: MessageCall) -> OpcodeResult {
+    let (args_offset, args_size) = memory_range(ctx, call.args_offset, call.args_size)?;
+    let (ret_offset, ret_size) = memory_range(ctx, call.ret_offset, call.ret_size)?;
+
+    expand_memory(ctx, args_offset, args_size)?;
+    expand_memory(ctx, ret_offset, ret_size)?;
+
+    let access_cost = account_access_cost(ctx, call.code_address);
+    charge_gas(ctx, access_cost)?;
+
+    if !call.value.is_zero() {
+        charge_gas(ctx, G_CALLVALUE)?;
+    }
+
+    // before Spurious Dragon, an account is new if it does not exist,
+    // even if no value is sent to it (EIP-161)
+    let new_account = if ctx.spec.is_enabled(SpecId::SpuriousDragon) {
+        !call.value.is_zero() && is_account_dead(&ctx.global_state, call.recipient)
+    } else {
+        !ctx.global_state.contains_key(&call.recipient)
+    };
+    if new_account {
+        charge_gas(ctx, G_NEWACCOUNT)?;
+    }
+
+    // before Tangerine Whistle, the requested gas is given as it is
+    let mut callee_gas = if ctx.spec.is_enabled(SpecId::TangerineWhistle) {
+        call.gas.min(all_but_one_64th(ctx.machine_state.gas))
+    } else {
+        call.gas
+    };
+    charge_gas(ctx, callee_gas)?;
+    if !call.value.is_zero() {
+        callee_gas += U256::from(G_CALLSTIPEND);
+    }
 
     let insufficient_balance = ctx
         .global_state
         .get(&ctx.input.address)
-        .unwrap_or(&AccountState::new()) // maybe an error for this?
-        .balance
-        < ctx.input.value;
-    let call_depth_limit_reached = ctx.input.depth == 1024;
+        .map(|account_state| account_state.balance)
+        .unwrap_or(U256::zero())
+        < call.value;
+    let call_depth_limit_reached = ctx.input.depth == CALL_DEPTH_LIMIT;
 
     if insufficient_balance || call_depth_limit_reached {
+        // the call fails without running, so there is no return data
+        ctx.machine_state.output = Vec::new();
+        ctx.machine_state.gas += callee_gas;
         ctx.machine_state.stack.push(U256::zero());
         return Ok(None);
     }
 
     // prepare call
-    let mut calldata: Vec<u8> = Vec::new();
+    let mut calldata: Vec<u8> = Vec::with_capacity(args_size);
     for i in args_offset..args_offset + args_size {
         calldata.push(*ctx.machine_state.memory.get(i).unwrap_or(&0))
     }
 
-    update_active_words_memory(ctx, args_offset + args_size);
-
     let old_input = ctx.input.clone();
     let old_machine_state = ctx.machine_state.clone();
+    let checkpoint = ctx.checkpoint();
 
-    ctx.input.bytecode = if let Some(account_state) = ctx.global_state.get(&address) {
-        account_state.code.clone()
-    } else {
-        Vec::new()
-    };
+    transfer(ctx, old_input.address, call.recipient, call.value);
+
+    ctx.input.sender = call.sender;
+    ctx.input.address = call.recipient;
+    ctx.input.value = call.apparent_value;
+    ctx.input.data = calldata;
+    ctx.input.write = call.write;
+    ctx.input.bytecode = ctx.code(call.code_address);
     ctx.input.depth += 1;
     ctx.machine_state = MachineState::new();
+    ctx.machine_state.gas = callee_gas;
 
     // call
-    let result = EVM::execute(ctx)?;
+    let result = EVM::execute_call(ctx, call.code_address);
 
     // restore current ctx and manage result
     ctx.input = old_input;
     ctx.machine_state = old_machine_state;
-    ctx.machine_state.output = result.output.unwrap_or(Vec::new());
-    ctx.machine_state.stack.push(U256::from(if result.success {
+    ctx.machine_state.gas += callee_gas - result.gas_used;
+    ctx.machine_state.output = result.output.unwrap_or_default();
+    if !result.success {
+        ctx.revert_to(checkpoint);
+    }
+    ctx.machine_state.stack.push(if result.success {
         U256::one()
     } else {
         U256::zero()
-    }));
-    ctx.accrued_substate.accessed_accounts.insert(address);
+    });
 
     let return_data_length = ret_size.min(ctx.machine_state.output.len());
 
     for i in 0..return_data_length {
         ctx.machine_state.memory[ret_offset + i] = *ctx.machine_state.output.get(i).unwrap_or(&0);
     }
-    update_active_words_memory(ctx, ret_offset + ret_size);
-
-    // sload then fails because the test supposes no particular account storage.
-    // the problem is the `to` setted up in the test of delegatecall.
 
     Ok(None)
 }
 
 /// 0xfa
+/// Like CALL, but without value and without the permission to modify
+/// the state in the callee and in its sub-contexts
 pub fn staticcall(ctx: &mut ExecutionContext) -> OpcodeResult {
-    // prepare the call with no write permission
-    let stack_items = pop_n(ctx, 2)?;
-    ctx.machine_state.stack.push(U256::zero()); // zero value;
-    stack_items
-        .iter()
-        .rev()
-        .for_each(|s_i| ctx.machine_state.stack.push(*s_i));
-
-    ctx.input.write = false;
-    call(ctx)
+    let stack_items = pop_n(ctx, 6)?;
+
+    let address = modulo_address_size(&stack_items[1]);
+
+    message_call(
+        ctx,
+        MessageCall {
+            gas: stack_items[0],
+            code_address: address,
+            recipient: address,
+            sender: ctx.input.address,
+            value: U256::zero(),
+            apparent_value: U256::zero(),
+            args_offset: stack_items[2],
+            args_size: stack_items[3],
+            ret_offset: stack_items[4],
+            ret_size: stack_items[5],
+            write: false,
+        },
+    )
 }
 
 /// 0xfd For this challenge, this is just a return with
@@ -324,32 +402,56 @@ pub fn invalid(_ctx: &mut ExecutionContext) -> OpcodeResult {
 pub fn selfdestruct(ctx: &mut ExecutionContext) -> OpcodeResult {
     let receiver_address = modulo_address_size(&pop_n(ctx, 1)?[0]);
 
-    // get contract balance
-    ctx.machine_state.stack.push(ctx.input.address);
-    let _ = balance(ctx);
-    let contract_balance = pop_n(ctx, 1)?[0];
-
-    // transfer balance
-    let receiver_account_state =
-        if let Some(account_state) = ctx.global_state.get_mut(&receiver_address) {
-            account_state
-        } else {
-            ctx.global_state
-                .insert(receiver_address, AccountState::new());
-            ctx.global_state.get_mut(&receiver_address).unwrap()
-        };
-
-    receiver_account_state.balance += contract_balance;
-
-    ctx.accrued_substate
-        .accessed_accounts
-        .insert(ctx.input.address);
-    ctx.accrued_substate
-        .accessed_accounts
-        .insert(receiver_address);
-    ctx.accrued_substate
-        .self_destruct_set
-        .insert(ctx.input.address);
+    let contract_balance = ctx
+        .global_state
+        .get(&ctx.input.address)
+        .map(|account_state| account_state.balance)
+        .unwrap_or(U256::zero());
+
+    // only a cold access to the receiver is charged
+    if account_access_cost(ctx, receiver_address) == G_COLDACCOUNTACCESS {
+        charge_gas(ctx, G_COLDACCOUNTACCESS)?;
+    }
+
+    // the receiver is new if it is dead and receives some value, or before
+    // Spurious Dragon if it does not exist (EIP-161). Creating it is free
+    // before Tangerine Whistle
+    let new_account = if ctx.spec.is_enabled(SpecId::SpuriousDragon) {
+        !contract_balance.is_zero() && is_account_dead(&ctx.global_state, receiver_address)
+    } else {
+        ctx.spec.is_enabled(SpecId::TangerineWhistle)
+            && !ctx.global_state.contains_key(&receiver_address)
+    };
+    if new_account {
+        charge_gas(ctx, G_NEWACCOUNT)?;
+    }
+
+    // the refund for destroying a contract was removed in London (EIP-3529)
+    if !ctx.spec.is_enabled(SpecId::London)
+        && !ctx
+            .accrued_substate
+            .self_destruct_set
+            .contains(&ctx.input.address)
+    {
+        *ctx.refund_balance_mut() += U256::from(R_SELFDESTRUCT_FRONTIER);
+    }
+
+    // transfer balance. If the receiver is the contract itself, the balance
+    // is burnt only when the account is deleted
+    ctx.account_mut(ctx.input.address).balance = U256::zero();
+    ctx.account_mut(receiver_address).balance += contract_balance;
+    ctx.touch_account(receiver_address);
+
+    // from Cancun, only a contract created in the same transaction
+    // is deleted (EIP-6780)
+    if !ctx.spec.is_enabled(SpecId::Cancun)
+        || ctx
+            .accrued_substate
+            .created_accounts
+            .contains(&ctx.input.address)
+    {
+        ctx.self_destruct(ctx.input.address);
+    }
 
     Ok(Some(Vec::new()))
 }
@@ -0,0 +1,134 @@
+// Operations on the alt_bn128 elliptic curve (EIP-196 and EIP-197),
+// repriced in Istanbul by EIP-1108
+
+use primitive_types::U256;
+use substrate_bn::{pairing_batch, AffineG1, AffineG2, Fq, Fq2, Fr, Group, Gt, G1, G2};
+
+use crate::evm::utils::{
+    gas::{
+        G_BN254ADD, G_BN254ADD_BYZANTIUM, G_BN254MUL, G_BN254MUL_BYZANTIUM, G_BN254PAIRING,
+        G_BN254PAIRINGPOINT, G_BN254PAIRINGPOINT_BYZANTIUM, G_BN254PAIRING_BYZANTIUM,
+    },
+    spec::SpecId,
+    types::{PrecompileOutput, PrecompileResult},
+};
+
+use super::right_padded;
+
+/// Size of the encoding of a pair of points of G1 and G2
+const PAIR_SIZE: usize = 192;
+
+/// 0x06
+pub fn ecadd(input: &[u8], gas: U256, spec: SpecId) -> PrecompileResult {
+    let cost = if spec.is_enabled(SpecId::Istanbul) {
+        G_BN254ADD
+    } else {
+        G_BN254ADD_BYZANTIUM
+    };
+    if gas < U256::from(cost) {
+        return None;
+    }
+
+    let input = right_padded(input, 0, 128);
+    let a = read_g1(&input[0..64])?;
+    let b = read_g1(&input[64..128])?;
+
+    Some(PrecompileOutput {
+        output: encode_g1(a + b),
+        gas_used: U256::from(cost),
+    })
+}
+
+/// 0x07
+pub fn ecmul(input: &[u8], gas: U256, spec: SpecId) -> PrecompileResult {
+    let cost = if spec.is_enabled(SpecId::Istanbul) {
+        G_BN254MUL
+    } else {
+        G_BN254MUL_BYZANTIUM
+    };
+    if gas < U256::from(cost) {
+        return None;
+    }
+
+    let input = right_padded(input, 0, 96);
+    let point = read_g1(&input[0..64])?;
+    let scalar = Fr::from_slice(&input[64..96]).ok()?;
+
+    Some(PrecompileOutput {
+        output: encode_g1(point * scalar),
+        gas_used: U256::from(cost),
+    })
+}
+
+/// 0x08
+/// Checks that the product of the pairings of the given pairs of points
+/// is one. The output is a 32 bytes boolean
+pub fn ecpairing(input: &[u8], gas: U256, spec: SpecId) -> PrecompileResult {
+    let (base_cost, pair_cost) = if spec.is_enabled(SpecId::Istanbul) {
+        (G_BN254PAIRING, G_BN254PAIRINGPOINT)
+    } else {
+        (G_BN254PAIRING_BYZANTIUM, G_BN254PAIRINGPOINT_BYZANTIUM)
+    };
+    let cost = base_cost + pair_cost * (input.len() / PAIR_SIZE) as u64;
+    if gas < U256::from(cost) || !input.len().is_multiple_of(PAIR_SIZE) {
+        return None;
+    }
+
+    let mut pairs: Vec<(G1, G2)> = Vec::with_capacity(input.len() / PAIR_SIZE);
+    for pair in input.chunks(PAIR_SIZE) {
+        pairs.push((read_g1(&pair[0..64])?, read_g2(&pair[64..192])?));
+    }
+
+    let success = pairing_batch(&pairs) == Gt::one();
+
+    let mut output = vec![0; 32];
+    output[31] = success as u8;
+
+    Some(PrecompileOutput {
+        output,
+        gas_used: U256::from(cost),
+    })
+}
+
+/// Decodes a point of G1 from its coordinates, where (0, 0)
+/// is the point at infinity
+fn read_g1(input: &[u8]) -> Option<G1> {
+    let x = Fq::from_slice(&input[0..32]).ok()?;
+    let y = Fq::from_slice(&input[32..64]).ok()?;
+
+    if x.is_zero() && y.is_zero() {
+        Some(G1::zero())
+    } else {
+        AffineG1::new(x, y).ok().map(G1::from)
+    }
+}
+
+/// Decodes a point of G2 from its coordinates, each of them
+/// encoded as its imaginary part followed by its real part
+fn read_g2(input: &[u8]) -> Option<G2> {
+    let x_imaginary = Fq::from_slice(&input[0..32]).ok()?;
+    let x_real = Fq::from_slice(&input[32..64]).ok()?;
+    let y_imaginary = Fq::from_slice(&input[64..96]).ok()?;
+    let y_real = Fq::from_slice(&input[96..128]).ok()?;
+
+    let x = Fq2::new(x_real, x_imaginary);
+    let y = Fq2::new(y_real, y_imaginary);
+
+    if x.is_zero() && y.is_zero() {
+        Some(G2::zero())
+    } else {
+        AffineG2::new(x, y).ok().map(G2::from)
+    }
+}
+
+fn encode_g1(point: G1) -> Vec<u8> {
+    let mut output = vec![0; 64];
+
+    // the point at infinity is encoded as (0, 0)
+    if let Some(point) = AffineG1::from_jacobian(point) {
+        point.x().to_big_endian(&mut output[0..32]).unwrap();
+        point.y().to_big_endian(&mut output[32..64]).unwrap();
+    }
+
+    output
+}
@@ -0,0 +1,59 @@
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+use primitive_types::U256;
+
+use crate::evm::utils::{
+    gas::G_ECRECOVER,
+    helpers::keccak256,
+    types::{PrecompileOutput, PrecompileResult},
+};
+
+use super::right_padded;
+
+/// 0x01
+/// Returns the address which signed a message hash, left-padded to 32 bytes.
+/// The input is made of the hash, `v`, `r` and `s`, each of them 32 bytes long.
+/// An invalid signature is not a failure, but the output is empty
+pub fn ecrecover(input: &[u8], gas: U256) -> PrecompileResult {
+    if gas < U256::from(G_ECRECOVER) {
+        return None;
+    }
+
+    let input = right_padded(input, 0, 128);
+
+    Some(PrecompileOutput {
+        output: recover(&input).unwrap_or_default(),
+        gas_used: U256::from(G_ECRECOVER),
+    })
+}
+
+fn recover(input: &[u8]) -> Option<Vec<u8>> {
+    let hash = &input[0..32];
+    let v = U256::from_big_endian(&input[32..64]);
+
+    // v is either 27 or 28
+    if v != U256::from(27) && v != U256::from(28) {
+        return None;
+    }
+    let mut recovery_id = RecoveryId::from_byte(v.low_u32() as u8 - 27)?;
+    let mut signature = Signature::from_slice(&input[64..128]).ok()?;
+
+    // signatures with a high `s` are valid, but the library only accepts
+    // the equivalent one with a low `s` and the opposite parity of `v`
+    if let Some(normalized_signature) = signature.normalize_s() {
+        signature = normalized_signature;
+        recovery_id = RecoveryId::new(!recovery_id.is_y_odd(), recovery_id.is_x_reduced());
+    }
+
+    let public_key = VerifyingKey::recover_from_prehash(hash, &signature, recovery_id).ok()?;
+
+    // the address is the last 20 bytes of the hash of the uncompressed
+    // public key, without its 0x04 prefix
+    let public_key = public_key.to_encoded_point(false);
+    let address = keccak256(&public_key.as_bytes()[1..]);
+
+    let mut output = [0_u8; 32];
+    address.to_big_endian(&mut output);
+    output[..12].fill(0);
+
+    Some(output.to_vec())
+}
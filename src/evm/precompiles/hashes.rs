@@ -0,0 +1,38 @@
+use primitive_types::U256;
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+
+use crate::evm::utils::{
+    gas::{words, G_RIPEMD160, G_RIPEMD160WORD, G_SHA256, G_SHA256WORD},
+    types::{PrecompileOutput, PrecompileResult},
+};
+
+/// 0x02
+pub fn sha256(input: &[u8], gas: U256) -> PrecompileResult {
+    let cost = G_SHA256 + G_SHA256WORD * words(input.len());
+    if gas < U256::from(cost) {
+        return None;
+    }
+
+    Some(PrecompileOutput {
+        output: Sha256::digest(input).to_vec(),
+        gas_used: U256::from(cost),
+    })
+}
+
+/// 0x03
+/// The 20 bytes hash is left-padded to 32 bytes
+pub fn ripemd160(input: &[u8], gas: U256) -> PrecompileResult {
+    let cost = G_RIPEMD160 + G_RIPEMD160WORD * words(input.len());
+    if gas < U256::from(cost) {
+        return None;
+    }
+
+    let mut output = vec![0; 12];
+    output.extend_from_slice(&Ripemd160::digest(input));
+
+    Some(PrecompileOutput {
+        output,
+        gas_used: U256::from(cost),
+    })
+}
@@ -0,0 +1,19 @@
+use primitive_types::U256;
+
+use crate::evm::utils::{
+    gas::{words, G_IDENTITY, G_IDENTITYWORD},
+    types::{PrecompileOutput, PrecompileResult},
+};
+
+/// 0x04
+pub fn identity(input: &[u8], gas: U256) -> PrecompileResult {
+    let cost = G_IDENTITY + G_IDENTITYWORD * words(input.len());
+    if gas < U256::from(cost) {
+        return None;
+    }
+
+    Some(PrecompileOutput {
+        output: input.to_vec(),
+        gas_used: U256::from(cost),
+    })
+}
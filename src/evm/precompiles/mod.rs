@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+
+use primitive_types::U256;
+
+use super::utils::{spec::SpecId, types::Precompiles};
+
+pub mod blake2f;
+pub mod bn254;
+pub mod ecrecover;
+pub mod hashes;
+pub mod identity;
+pub mod modexp;
+pub mod point_evaluation;
+
+/// Returns the precompiled contracts available under `spec`, indexed by their address
+pub fn get_precompiles(spec: SpecId) -> Precompiles {
+    let mut precompiles: Precompiles = HashMap::new();
+
+    precompiles.insert(U256::from(0x01), Box::new(ecrecover::ecrecover));
+    precompiles.insert(U256::from(0x02), Box::new(hashes::sha256));
+    precompiles.insert(U256::from(0x03), Box::new(hashes::ripemd160));
+    precompiles.insert(U256::from(0x04), Box::new(identity::identity));
+
+    // EIP-196, EIP-197 and EIP-198
+    if spec.is_enabled(SpecId::Byzantium) {
+        precompiles.insert(
+            U256::from(0x05),
+            Box::new(move |input: &[u8], gas| modexp::modexp(input, gas, spec)),
+        );
+        precompiles.insert(
+            U256::from(0x06),
+            Box::new(move |input: &[u8], gas| bn254::ecadd(input, gas, spec)),
+        );
+        precompiles.insert(
+            U256::from(0x07),
+            Box::new(move |input: &[u8], gas| bn254::ecmul(input, gas, spec)),
+        );
+        precompiles.insert(
+            U256::from(0x08),
+            Box::new(move |input: &[u8], gas| bn254::ecpairing(input, gas, spec)),
+        );
+    }
+
+    // EIP-152
+    if spec.is_enabled(SpecId::Istanbul) {
+        precompiles.insert(U256::from(0x09), Box::new(blake2f::blake2f));
+    }
+
+    // EIP-4844
+    if spec.is_enabled(SpecId::Cancun) {
+        precompiles.insert(
+            U256::from(0x0a),
+            Box::new(point_evaluation::point_evaluation),
+        );
+    }
+
+    precompiles
+}
+
+/// Returns `size` bytes of `input` starting from `offset`, where
+/// the bytes past the end of `input` are zero
+pub fn right_padded(input: &[u8], offset: usize, size: usize) -> Vec<u8> {
+    let mut result = vec![0; size];
+
+    if offset < input.len() {
+        let end = input.len().min(offset.saturating_add(size));
+        result[..end - offset].copy_from_slice(&input[offset..end]);
+    }
+
+    result
+}
@@ -0,0 +1,104 @@
+use primitive_types::U256;
+
+use crate::evm::utils::{
+    gas::{G_MODEXP_MIN, G_QUADDIVISOR, G_QUADDIVISOR_BYZANTIUM},
+    spec::SpecId,
+    types::{PrecompileOutput, PrecompileResult},
+};
+
+use super::right_padded;
+
+/// 0x05
+/// Computes `base ^ exponent % modulus`. The input is made of the lengths
+/// of the three operands, each of them 32 bytes long, then the operands.
+/// Gas is priced as in EIP-198, then as in EIP-2565 from Berlin
+pub fn modexp(input: &[u8], gas: U256, spec: SpecId) -> PrecompileResult {
+    let base_length = U256::from_big_endian(&right_padded(input, 0, 32));
+    let exponent_length = U256::from_big_endian(&right_padded(input, 32, 32));
+    let modulus_length = U256::from_big_endian(&right_padded(input, 64, 32));
+
+    // only the first 32 bytes of the exponent are needed to price it
+    let exponent_head = match usize::try_from(base_length) {
+        Ok(base_length) => {
+            let head_length = exponent_length.min(U256::from(32)).as_usize();
+            let head = right_padded(input, 96_usize.saturating_add(base_length), head_length);
+            U256::from_big_endian(&head)
+        }
+        Err(_) => U256::zero(),
+    };
+
+    let cost = modexp_cost(
+        base_length,
+        exponent_length,
+        modulus_length,
+        exponent_head,
+        spec,
+    );
+    if gas < cost {
+        return None;
+    }
+
+    // operands which do not fit in memory cannot be read
+    let base_length = usize::try_from(base_length).ok()?;
+    let exponent_length = usize::try_from(exponent_length).ok()?;
+    let modulus_length = usize::try_from(modulus_length).ok()?;
+
+    let output = if modulus_length == 0 {
+        Vec::new()
+    } else {
+        let base = right_padded(input, 96, base_length);
+        let exponent = right_padded(input, 96 + base_length, exponent_length);
+        let modulus = right_padded(input, 96 + base_length + exponent_length, modulus_length);
+
+        // the result is left-padded to the length of the modulus
+        let result = aurora_engine_modexp::modexp(&base, &exponent, &modulus);
+        let mut output = vec![0; modulus_length - result.len()];
+        output.extend_from_slice(&result);
+        output
+    };
+
+    Some(PrecompileOutput {
+        output,
+        gas_used: cost,
+    })
+}
+
+fn modexp_cost(
+    base_length: U256,
+    exponent_length: U256,
+    modulus_length: U256,
+    exponent_head: U256,
+    spec: SpecId,
+) -> U256 {
+    let max_length = base_length.max(modulus_length);
+
+    let head_bits = U256::from(exponent_head.bits().saturating_sub(1));
+    let iteration_count = if exponent_length <= U256::from(32) {
+        head_bits
+    } else {
+        (exponent_length - 32)
+            .saturating_mul(U256::from(8))
+            .saturating_add(head_bits)
+    }
+    .max(U256::one());
+
+    if spec.is_enabled(SpecId::Berlin) {
+        let words = max_length.saturating_add(U256::from(7)) / 8;
+        let multiplication_complexity = words.saturating_mul(words);
+        let cost = multiplication_complexity.saturating_mul(iteration_count) / G_QUADDIVISOR;
+
+        cost.max(U256::from(G_MODEXP_MIN))
+    } else {
+        let multiplication_complexity = if max_length <= U256::from(64) {
+            max_length * max_length
+        } else if max_length <= U256::from(1024) {
+            max_length * max_length / 4 + max_length * 96 - 3072
+        } else {
+            (max_length.saturating_mul(max_length) / 16)
+                .saturating_add(max_length.saturating_mul(U256::from(480)))
+                - 199680
+        };
+
+        multiplication_complexity.saturating_mul(iteration_count) / G_QUADDIVISOR_BYZANTIUM
+    }
+}
@@ -0,0 +1,68 @@
+// The point evaluation precompiled contract of EIP-4844, which verifies
+// that a blob, identified by the versioned hash of its KZG commitment,
+// evaluates to `y` at `z`
+
+use c_kzg::{ethereum_kzg_settings, Bytes32, Bytes48};
+use primitive_types::U256;
+use sha2::{Digest, Sha256};
+
+use crate::evm::utils::{
+    constants::VERSIONED_HASH_VERSION_KZG,
+    gas::G_POINTEVALUATION,
+    types::{PrecompileOutput, PrecompileResult},
+};
+
+/// Size of the input: versioned hash, z, y, commitment and proof
+const INPUT_SIZE: usize = 32 + 32 + 32 + 48 + 48;
+
+const FIELD_ELEMENTS_PER_BLOB: u64 = 4096;
+
+/// The modulus of the scalar field of BLS12-381
+const BLS_MODULUS: [u8; 32] = [
+    0x73, 0xed, 0xa7, 0x53, 0x29, 0x9d, 0x7d, 0x48, 0x33, 0x39, 0xd8, 0x08, 0x09, 0xa1, 0xd8, 0x05,
+    0x53, 0xbd, 0xa4, 0x02, 0xff, 0xfe, 0x5b, 0xfe, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x01,
+];
+
+/// 0x0a
+/// The output is made of the number of field elements in a blob
+/// and of the modulus of the field, both 32 bytes long.
+/// The trusted setup is the one of the mainnet, bundled with c-kzg
+pub fn point_evaluation(input: &[u8], gas: U256) -> PrecompileResult {
+    if gas < U256::from(G_POINTEVALUATION) || input.len() != INPUT_SIZE {
+        return None;
+    }
+
+    let versioned_hash = &input[0..32];
+    let z = Bytes32::from_bytes(&input[32..64]).ok()?;
+    let y = Bytes32::from_bytes(&input[64..96]).ok()?;
+    let commitment = Bytes48::from_bytes(&input[96..144]).ok()?;
+    let proof = Bytes48::from_bytes(&input[144..192]).ok()?;
+
+    if versioned_hash != kzg_to_versioned_hash(&input[96..144]) {
+        return None;
+    }
+
+    let verified = ethereum_kzg_settings(0)
+        .verify_kzg_proof(&commitment, &z, &y, &proof)
+        .ok()?;
+    if !verified {
+        return None;
+    }
+
+    let mut output = vec![0; 32];
+    U256::from(FIELD_ELEMENTS_PER_BLOB).to_big_endian(&mut output);
+    output.extend_from_slice(&BLS_MODULUS);
+
+    Some(PrecompileOutput {
+        output,
+        gas_used: U256::from(G_POINTEVALUATION),
+    })
+}
+
+/// The SHA-256 hash of the commitment, whose first byte is replaced by the version
+fn kzg_to_versioned_hash(commitment: &[u8]) -> Vec<u8> {
+    let mut hash = Sha256::digest(commitment).to_vec();
+    hash[0] = VERSIONED_HASH_VERSION_KZG;
+
+    hash
+}
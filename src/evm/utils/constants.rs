@@ -2,6 +2,7 @@ use primitive_types::U256;
 
 pub const STOP: u8 = 0x00;
 pub const SSTORE: u8 = 0x55;
+pub const TSTORE: u8 = 0x5d;
 pub const JUMPDEST: u8 = 0x5b;
 pub const PUSH_0: u8 = 0x5f;
 pub const PUSH_1: u8 = 0x60;
@@ -16,8 +17,9 @@ pub const REVERT: u8 = 0xfd;
 pub const INVALID: u8 = 0xfe;
 pub const SELFDESTRUCT: u8 = 0xff;
 
-pub const NO_STATIC_OPCODES: [u8; 9] = [
+pub const NO_STATIC_OPCODES: [u8; 10] = [
     SSTORE,
+    TSTORE,
     CREATE,
     CREATE2,
     LOG_0,
@@ -28,10 +30,33 @@ pub const NO_STATIC_OPCODES: [u8; 9] = [
     SELFDESTRUCT,
 ];
 
+/// The chain ID of the Ethereum mainnet, used when none is configured (EIP-155)
 pub const CHAIN_ID: u8 = 0x01;
 
 pub const CALL_DEPTH_LIMIT: usize = 1024;
 
+/// The maximum number of items on the stack
+pub const STACK_LIMIT: usize = 1024;
+
+/// The number of most recent blocks whose hash is available to BLOCKHASH
+pub const BLOCK_HASH_HISTORY: usize = 256;
+
+/// The maximum size of the code of a contract (EIP-170)
+pub const MAX_CODE_SIZE: usize = 24576;
+
+/// The first byte of the code of a contract, reserved for the
+/// EVM Object Format (EIP-3541)
+pub const EOF_MAGIC: u8 = 0xef;
+
+/// The maximum size of the initialisation code of a contract (EIP-3860)
+pub const MAX_INITCODE_SIZE: usize = 49152;
+
+/// The maximum number of blobs in a block (EIP-4844)
+pub const MAX_BLOBS_PER_BLOCK: usize = 6;
+
+/// The first byte of the versioned hash of a KZG commitment (EIP-4844)
+pub const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+
 pub const BYTES_IN_U256_FROM_ZERO: usize = 0x1f;
 
 /// c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470
@@ -42,6 +67,15 @@ pub const KECCAK_EMPTY: U256 = U256([
     0xc5d2460186f7233c,
 ]);
 
+/// 56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421,
+/// the root of an empty trie
+pub const EMPTY_TRIE_ROOT: U256 = U256([
+    0x01622fb5e363b421,
+    0x5b48e01b996cadc0,
+    0xff8345e692c0f86e,
+    0x56e81f171bcc55a6,
+]);
+
 pub const TEST_CONTRACT_ADDRESS: U256 =
     //    U256([0x0, 0x7C07e01e98, 0x767d56ac03e8037C, 0x8731d54E9D02c286]);
     U256([0x0, 0x1000000000, 0x0000000000000000, 0x0000000000000aaa]);
@@ -0,0 +1,84 @@
+use std::{fmt::Debug, sync::Arc};
+
+use primitive_types::U256;
+
+use super::types::{AccountState, BlockHashes, CodeStore, GlobalState, Storage};
+
+/// The world state the execution starts from. Accounts and storage slots are
+/// read from it the first time they are accessed, and from then on they live
+/// in `ExecutionContext::global_state`, which holds every change made to them
+pub trait Database: Debug {
+    /// Returns the nonce, balance and code hash of the account at `address`,
+    /// or `None` if it does not exist. Its storage is left empty,
+    /// since it is read slot by slot with `storage`
+    fn basic(&self, address: U256) -> Option<AccountState>;
+
+    /// Returns the code whose Keccak-256 hash is `code_hash`
+    fn code_by_hash(&self, code_hash: U256) -> Arc<[u8]>;
+
+    /// Returns the value of the storage slot `key` of the account at `address`
+    fn storage(&self, address: U256, key: U256) -> U256;
+
+    /// Whether the account at `address` has a storage slot which is not zero
+    fn has_storage(&self, address: U256) -> bool;
+
+    /// Returns the hash of the block `number`, or zero if it is unknown
+    fn block_hash(&self, number: U256) -> U256;
+
+    /// Returns every account together with its storage, or `None` if
+    /// the database cannot list them, as when it is backed by a node
+    fn accounts(&self) -> Option<GlobalState> {
+        None
+    }
+}
+
+/// A database which keeps the whole world state in memory
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryDatabase {
+    pub accounts: GlobalState,
+    pub code_store: CodeStore,
+    pub block_hashes: BlockHashes,
+}
+
+impl Database for InMemoryDatabase {
+    fn basic(&self, address: U256) -> Option<AccountState> {
+        self.accounts
+            .get(&address)
+            .map(|account_state| AccountState {
+                storage: Storage::new(),
+                ..account_state.clone()
+            })
+    }
+
+    fn code_by_hash(&self, code_hash: U256) -> Arc<[u8]> {
+        self.code_store
+            .get(&code_hash)
+            .cloned()
+            .unwrap_or_else(|| Arc::from([]))
+    }
+
+    fn storage(&self, address: U256, key: U256) -> U256 {
+        self.accounts
+            .get(&address)
+            .and_then(|account_state| account_state.storage.get(&key))
+            .copied()
+            .unwrap_or(U256::zero())
+    }
+
+    fn has_storage(&self, address: U256) -> bool {
+        self.accounts.get(&address).is_some_and(|account_state| {
+            account_state.storage.values().any(|value| !value.is_zero())
+        })
+    }
+
+    fn block_hash(&self, number: U256) -> U256 {
+        self.block_hashes
+            .get(&number)
+            .copied()
+            .unwrap_or(U256::zero())
+    }
+
+    fn accounts(&self) -> Option<GlobalState> {
+        Some(self.accounts.clone())
+    }
+}
@@ -2,82 +2,176 @@ use std::{error::Error, fmt};
 
 use primitive_types::U256;
 
-use super::types::ExecutionContext;
-
+#[derive(Debug)]
 pub enum EVMError {
-    NoBytecodeError(ExecutionContext),
-    FromStrRadixError(String, ExecutionContext),
-    NoOpcodeError(u8, ExecutionContext),
-    EmptyStackError(ExecutionContext),
-    U256ToUSizeError(U256, ExecutionContext),
-    U256ToU8Error(U256, ExecutionContext),
-    InvalidJumpdestError(usize, ExecutionContext),
+    NoBytecodeError(usize),
+    FromStrRadixError(String),
+    NoOpcodeError(u8),
+    EmptyStackError,
+    U256ToUSizeError(U256),
+    U256ToU8Error(U256),
+    InvalidJumpdestError(usize),
+    OutOfGasError(usize),
+    WriteProtectionError(usize),
+    InitcodeSizeError(usize),
+    ReturnDataOutOfBoundsError(usize),
+    InvalidTransactionError(InvalidTransaction),
 }
 
-impl fmt::Display for EVMError {
+/// The reasons why a transaction cannot be executed. An invalid
+/// transaction leaves the state untouched and pays no fee
+#[derive(Debug)]
+pub enum InvalidTransaction {
+    /// the type of the transaction is not supported under the current spec
+    TypeNotSupported,
+    /// the nonce of the sender and the one of the transaction
+    NonceMismatch(usize, usize),
+    /// the nonce of the sender is the largest one, so it cannot be increased (EIP-2681)
+    NonceOverflow,
+    /// the sender has code (EIP-3607)
+    SenderNotEOA,
+    /// the balance of the sender and the maximum cost of the transaction
+    InsufficientFunds(U256, U256),
+    /// the intrinsic gas and the gas limit of the transaction
+    IntrinsicGasTooLow(U256, U256),
+    /// the gas limit of the transaction and the one of the block
+    GasLimitAboveBlock(U256, U256),
+    /// the maximum fee per gas and the base fee of the block
+    FeeBelowBaseFee(U256, U256),
+    /// the priority fee is greater than the maximum fee per gas
+    PriorityFeeAboveMaxFee,
+    /// the size of the initialisation code of a contract creation
+    InitcodeSize(usize),
+    /// a blob transaction cannot create a contract
+    BlobCreation,
+    /// the number of blobs is zero or greater than the maximum per block
+    BlobCount(usize),
+    /// a versioned hash does not start with the version of KZG commitments
+    BlobHashVersion(U256),
+    /// the maximum fee per blob gas and the blob base fee of the block
+    BlobFeeBelowBlobBaseFee(U256, U256),
+}
+
+impl fmt::Display for InvalidTransaction {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            EVMError::EmptyStackError(_) => {
-                write!(f, "cannot pop from empty stack")
+            InvalidTransaction::TypeNotSupported => {
+                write!(f, "transaction type not supported")
             }
-            EVMError::NoOpcodeError(op, _) => {
-                write!(f, "cannot find opcode {:x?}", op)
+            InvalidTransaction::NonceMismatch(expected, got) => {
+                write!(
+                    f,
+                    "nonce {} does not match the sender's nonce {}",
+                    got, expected
+                )
             }
-            EVMError::NoBytecodeError(ctx) => {
-                write!(f, "cannot find code at pc {}", ctx.machine_state.pc)
+            InvalidTransaction::NonceOverflow => {
+                write!(f, "nonce of the sender cannot be increased")
             }
-            EVMError::FromStrRadixError(..) => {
-                write!(f, "cannot parse string to hex")
+            InvalidTransaction::SenderNotEOA => {
+                write!(f, "sender is not an externally owned account")
             }
-            EVMError::U256ToUSizeError(val, _) => {
-                write!(f, "cannot convert from U256 {:x?} to usize", val)
+            InvalidTransaction::InsufficientFunds(balance, cost) => {
+                write!(
+                    f,
+                    "balance {} is lower than the maximum cost {}",
+                    balance, cost
+                )
             }
-            EVMError::U256ToU8Error(val, _) => {
-                write!(f, "cannot convert from U256 {:x?} to u8", val)
+            InvalidTransaction::IntrinsicGasTooLow(intrinsic_gas, gas_limit) => {
+                write!(
+                    f,
+                    "gas limit {} is lower than the intrinsic gas {}",
+                    gas_limit, intrinsic_gas
+                )
             }
-            EVMError::InvalidJumpdestError(val, _) => {
-                write!(f, "cannot jump to opcode {:x?}", val)
+            InvalidTransaction::GasLimitAboveBlock(gas_limit, block_gas_limit) => {
+                write!(
+                    f,
+                    "gas limit {} is greater than the block gas limit {}",
+                    gas_limit, block_gas_limit
+                )
+            }
+            InvalidTransaction::FeeBelowBaseFee(max_fee, base_fee) => {
+                write!(
+                    f,
+                    "maximum fee per gas {} is lower than the base fee {}",
+                    max_fee, base_fee
+                )
+            }
+            InvalidTransaction::PriorityFeeAboveMaxFee => {
+                write!(f, "priority fee is greater than the maximum fee per gas")
+            }
+            InvalidTransaction::InitcodeSize(size) => {
+                write!(f, "initialisation code of size {} is too large", size)
+            }
+            InvalidTransaction::BlobCreation => {
+                write!(f, "blob transaction cannot create a contract")
+            }
+            InvalidTransaction::BlobCount(count) => {
+                write!(f, "invalid number of blobs {}", count)
+            }
+            InvalidTransaction::BlobHashVersion(hash) => {
+                write!(f, "invalid version of the blob hash {:x}", hash)
+            }
+            InvalidTransaction::BlobFeeBelowBlobBaseFee(max_fee, blob_base_fee) => {
+                write!(
+                    f,
+                    "maximum fee per blob gas {} is lower than the blob base fee {}",
+                    max_fee, blob_base_fee
+                )
             }
         }
     }
 }
 
-impl fmt::Debug for EVMError {
+impl fmt::Display for EVMError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            EVMError::NoBytecodeError(ctx) => {
-                write!(f, "NoBytecodeError\n    ctx: {:x?}", ctx)
+            EVMError::EmptyStackError => {
+                write!(f, "cannot pop from empty stack")
             }
-            EVMError::FromStrRadixError(str, ctx) => {
-                write!(
-                    f,
-                    "FromStrRadixError\n    str: {},\n    ctx: {:x?}",
-                    str, ctx
-                )
+            EVMError::NoOpcodeError(op) => {
+                write!(f, "cannot find opcode {:x?}", op)
             }
-            EVMError::NoOpcodeError(op, ctx) => {
-                write!(f, "NoOpcodeError\n    op: {:x},\n    ctx: {:x?}", op, ctx)
+            EVMError::NoBytecodeError(pc) => {
+                write!(f, "cannot find code at pc {}", pc)
             }
-            EVMError::EmptyStackError(ctx) => {
-                write!(f, "EmptyStackError\n    ctx: {:x?}", ctx)
+            EVMError::FromStrRadixError(..) => {
+                write!(f, "cannot parse string to hex")
+            }
+            EVMError::U256ToUSizeError(val) => {
+                write!(f, "cannot convert from U256 {:x?} to usize", val)
             }
-            EVMError::U256ToUSizeError(val, ctx) => {
+            EVMError::U256ToU8Error(val) => {
+                write!(f, "cannot convert from U256 {:x?} to u8", val)
+            }
+            EVMError::InvalidJumpdestError(val) => {
+                write!(f, "cannot jump to opcode {:x?}", val)
+            }
+            EVMError::OutOfGasError(pc) => {
+                write!(f, "out of gas at pc {}", pc)
+            }
+            EVMError::WriteProtectionError(pc) => {
                 write!(
                     f,
-                    "U256ToUSizeError\n    val: {:x}\n    ctx: {:x?}",
-                    val, ctx
+                    "cannot modify the state in a static context at pc {}",
+                    pc
                 )
             }
-            EVMError::U256ToU8Error(val, ctx) => {
-                write!(f, "U256ToU8Error\n    val: {:x}\n    ctx: {:x?}", val, ctx)
+            EVMError::InitcodeSizeError(size) => {
+                write!(f, "initialisation code of size {} is too large", size)
             }
-            EVMError::InvalidJumpdestError(val, ctx) => {
+            EVMError::ReturnDataOutOfBoundsError(pc) => {
                 write!(
                     f,
-                    "InvalidJumpdestError\n    val: {:x}\n    ctx: {:x?}",
-                    val, ctx
+                    "cannot read past the end of the return data at pc {}",
+                    pc
                 )
             }
+            EVMError::InvalidTransactionError(reason) => {
+                write!(f, "invalid transaction: {}", reason)
+            }
         }
     }
 }
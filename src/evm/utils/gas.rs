@@ -0,0 +1,321 @@
+use primitive_types::U256;
+
+use super::{
+    errors::EVMError,
+    spec::SpecId,
+    types::{AccessList, ExecutionContext},
+};
+
+// Fee schedule, see Appendix G of the yellow paper
+
+pub const G_ZERO: u64 = 0;
+pub const G_JUMPDEST: u64 = 1;
+pub const G_BASE: u64 = 2;
+pub const G_VERYLOW: u64 = 3;
+pub const G_LOW: u64 = 5;
+pub const G_MID: u64 = 8;
+pub const G_HIGH: u64 = 10;
+pub const G_WARMACCESS: u64 = 100;
+pub const G_COLDACCOUNTACCESS: u64 = 2600;
+pub const G_COLDSLOAD: u64 = 2100;
+pub const G_SSET: u64 = 20000;
+pub const G_SRESET: u64 = 2900;
+pub const R_SCLEAR: u64 = 4800;
+pub const G_SELFDESTRUCT: u64 = 5000;
+pub const G_CREATE: u64 = 32000;
+pub const G_CODEDEPOSIT: u64 = 200;
+pub const G_CALLVALUE: u64 = 9000;
+pub const G_CALLSTIPEND: u64 = 2300;
+pub const G_NEWACCOUNT: u64 = 25000;
+pub const G_EXP: u64 = 10;
+pub const G_EXPBYTE: u64 = 50;
+pub const G_MEMORY: u64 = 3;
+pub const G_LOG: u64 = 375;
+pub const G_LOGDATA: u64 = 8;
+pub const G_LOGTOPIC: u64 = 375;
+pub const G_KECCAK256: u64 = 30;
+pub const G_KECCAK256WORD: u64 = 6;
+pub const G_COPY: u64 = 3;
+pub const G_BLOCKHASH: u64 = 20;
+pub const G_INITCODEWORD: u64 = 2;
+pub const G_TRANSACTION: u64 = 21000;
+pub const G_TXCREATE: u64 = 32000;
+pub const G_TXDATAZERO: u64 = 4;
+pub const G_TXDATANONZERO: u64 = 16;
+pub const G_ACCESSLISTADDRESS: u64 = 2400;
+pub const G_ACCESSLISTSTORAGE: u64 = 1900;
+
+/// The amount of blob gas used by each blob of a transaction (EIP-4844)
+pub const GAS_PER_BLOB: u64 = 131072;
+pub const MIN_BASE_FEE_PER_BLOB_GAS: u64 = 1;
+pub const BLOB_BASE_FEE_UPDATE_FRACTION: u64 = 3338477;
+
+// Fee schedule of the precompiled contracts, see Appendix E of the yellow paper
+
+pub const G_ECRECOVER: u64 = 3000;
+pub const G_SHA256: u64 = 60;
+pub const G_SHA256WORD: u64 = 12;
+pub const G_RIPEMD160: u64 = 600;
+pub const G_RIPEMD160WORD: u64 = 120;
+pub const G_IDENTITY: u64 = 15;
+pub const G_IDENTITYWORD: u64 = 3;
+pub const G_MODEXP_MIN: u64 = 200;
+pub const G_QUADDIVISOR: u64 = 3;
+pub const G_BN254ADD: u64 = 150;
+pub const G_BN254MUL: u64 = 6000;
+pub const G_BN254PAIRING: u64 = 45000;
+pub const G_BN254PAIRINGPOINT: u64 = 34000;
+pub const G_BN254ADD_BYZANTIUM: u64 = 500;
+pub const G_BN254MUL_BYZANTIUM: u64 = 40000;
+pub const G_BN254PAIRING_BYZANTIUM: u64 = 100000;
+pub const G_BN254PAIRINGPOINT_BYZANTIUM: u64 = 80000;
+pub const G_QUADDIVISOR_BYZANTIUM: u64 = 20;
+pub const G_BLAKE2FROUND: u64 = 1;
+pub const G_POINTEVALUATION: u64 = 50000;
+
+/// At most `gas_used / MAX_REFUND_QUOTIENT` is refunded (EIP-3529)
+pub const MAX_REFUND_QUOTIENT: u64 = 5;
+
+// Costs which were later changed, named after the hard fork which introduced them
+
+pub const G_SLOAD_FRONTIER: u64 = 50;
+pub const G_SLOAD_TANGERINEWHISTLE: u64 = 200;
+pub const G_SLOAD_ISTANBUL: u64 = 800;
+pub const G_BALANCE_FRONTIER: u64 = 20;
+pub const G_BALANCE_TANGERINEWHISTLE: u64 = 400;
+pub const G_BALANCE_ISTANBUL: u64 = 700;
+pub const G_EXTCODE_FRONTIER: u64 = 20;
+pub const G_EXTCODE_TANGERINEWHISTLE: u64 = 700;
+pub const G_EXTCODEHASH_PETERSBURG: u64 = 400;
+pub const G_EXTCODEHASH_ISTANBUL: u64 = 700;
+pub const G_CALL_FRONTIER: u64 = 40;
+pub const G_CALL_TANGERINEWHISTLE: u64 = 700;
+pub const G_EXPBYTE_FRONTIER: u64 = 10;
+pub const G_SRESET_FRONTIER: u64 = 5000;
+pub const G_TXDATANONZERO_FRONTIER: u64 = 68;
+pub const R_SCLEAR_FRONTIER: u64 = 15000;
+pub const R_SELFDESTRUCT_FRONTIER: u64 = 24000;
+pub const MAX_REFUND_QUOTIENT_FRONTIER: u64 = 2;
+
+/// Returns the part of the cost of an opcode which does not depend on
+/// its operands under `spec`. The dynamic part, if any, is charged by the opcode itself
+pub fn static_cost(opcode: u8, spec: SpecId) -> u64 {
+    match opcode {
+        // W_zero
+        0x00 | 0xf3 | 0xfd => G_ZERO,
+        // W_base
+        0x30
+        | 0x32..=0x34
+        | 0x36
+        | 0x38
+        | 0x3a
+        | 0x3d
+        | 0x41..=0x46
+        | 0x48
+        | 0x4a
+        | 0x50
+        | 0x58
+        | 0x59
+        | 0x5a
+        | 0x5f => G_BASE,
+        // W_verylow
+        0x01
+        | 0x03
+        | 0x10..=0x1d
+        | 0x35
+        | 0x37
+        | 0x39
+        | 0x3e
+        | 0x49
+        | 0x51..=0x53
+        | 0x5e
+        | 0x60..=0x9f => G_VERYLOW,
+        // W_low
+        0x02 | 0x04..=0x07 | 0x0b | 0x47 => G_LOW,
+        // W_mid
+        0x08 | 0x09 | 0x56 => G_MID,
+        // W_high
+        0x57 => G_HIGH,
+        0x0a => G_EXP,
+        0x20 => G_KECCAK256,
+        0x40 => G_BLOCKHASH,
+        0x5b => G_JUMPDEST,
+        // transient storage is always warm (EIP-1153)
+        0x5c | 0x5d => G_WARMACCESS,
+        0xa0..=0xa4 => G_LOG,
+        0xf0 | 0xf5 => G_CREATE,
+        0xff if spec.is_enabled(SpecId::TangerineWhistle) => G_SELFDESTRUCT,
+        // before Berlin, accessing accounts or storage has a fixed cost
+        0x31 | 0x3b | 0x3c | 0x3f | 0x54 | 0xf1 | 0xf2 | 0xf4 | 0xfa
+            if !spec.is_enabled(SpecId::Berlin) =>
+        {
+            fixed_access_cost(opcode, spec)
+        }
+        // opcodes accessing accounts or storage pay the warm or cold access
+        // cost (EIP-2929), INVALID consumes all the gas
+        _ => G_ZERO,
+    }
+}
+
+/// The cost of the opcodes accessing accounts or storage before
+/// the warm and cold accesses of Berlin, as repriced by EIP-150 and EIP-1884
+fn fixed_access_cost(opcode: u8, spec: SpecId) -> u64 {
+    let tangerine_whistle = spec.is_enabled(SpecId::TangerineWhistle);
+    let istanbul = spec.is_enabled(SpecId::Istanbul);
+
+    match opcode {
+        // BALANCE
+        0x31 if istanbul => G_BALANCE_ISTANBUL,
+        0x31 if tangerine_whistle => G_BALANCE_TANGERINEWHISTLE,
+        0x31 => G_BALANCE_FRONTIER,
+        // EXTCODESIZE, EXTCODECOPY
+        0x3b | 0x3c if tangerine_whistle => G_EXTCODE_TANGERINEWHISTLE,
+        0x3b | 0x3c => G_EXTCODE_FRONTIER,
+        // EXTCODEHASH
+        0x3f if istanbul => G_EXTCODEHASH_ISTANBUL,
+        0x3f => G_EXTCODEHASH_PETERSBURG,
+        // SLOAD
+        0x54 if istanbul => G_SLOAD_ISTANBUL,
+        0x54 if tangerine_whistle => G_SLOAD_TANGERINEWHISTLE,
+        0x54 => G_SLOAD_FRONTIER,
+        // the CALL family
+        _ if tangerine_whistle => G_CALL_TANGERINEWHISTLE,
+        _ => G_CALL_FRONTIER,
+    }
+}
+
+/// Models the memory cost function C_mem in the yellow paper,
+/// where `words` is the number of active words in memory
+pub fn memory_cost(words: usize) -> u64 {
+    let words = words as u128;
+    let cost = G_MEMORY as u128 * words + words * words / 512;
+
+    u64::try_from(cost).unwrap_or(u64::MAX)
+}
+
+/// Number of 32-bytes words needed to store `size` bytes
+pub fn words(size: usize) -> u64 {
+    size.div_ceil(32) as u64
+}
+
+/// Returns the amount of gas given back at the end of a transaction which
+/// used `gas_used` and accrued `refund_balance` in its substate
+pub fn capped_refund(refund_balance: U256, gas_used: U256, spec: SpecId) -> U256 {
+    let quotient = if spec.is_enabled(SpecId::London) {
+        MAX_REFUND_QUOTIENT
+    } else {
+        MAX_REFUND_QUOTIENT_FRONTIER
+    };
+
+    refund_balance.min(gas_used / quotient)
+}
+
+/// Returns the gas a transaction pays before any of its code is executed:
+/// the base cost, the cost of its data and of its access list (EIP-2930),
+/// and, for a contract creation, the cost of creating the account
+/// from Homestead (EIP-2) and of the initialisation code words from
+/// Shanghai (EIP-3860). Non-zero data bytes are cheaper from Istanbul (EIP-2028)
+pub fn intrinsic_gas(data: &[u8], create: bool, access_list: &AccessList, spec: SpecId) -> U256 {
+    let data_nonzero_cost = if spec.is_enabled(SpecId::Istanbul) {
+        G_TXDATANONZERO
+    } else {
+        G_TXDATANONZERO_FRONTIER
+    };
+    let data_cost: u64 = data
+        .iter()
+        .map(|byte| {
+            if *byte == 0 {
+                G_TXDATAZERO
+            } else {
+                data_nonzero_cost
+            }
+        })
+        .sum();
+
+    let access_list_cost: u64 = access_list
+        .iter()
+        .map(|(_, keys)| G_ACCESSLISTADDRESS + G_ACCESSLISTSTORAGE * keys.len() as u64)
+        .sum();
+
+    let mut create_cost = G_ZERO;
+    if create && spec.is_enabled(SpecId::Homestead) {
+        create_cost += G_TXCREATE;
+    }
+    if create && spec.is_enabled(SpecId::Shanghai) {
+        create_cost += G_INITCODEWORD * words(data.len());
+    }
+
+    U256::from(G_TRANSACTION) + data_cost + access_list_cost + create_cost
+}
+
+/// Returns the blob base fee of a block given the blob gas used above
+/// the target by its ancestors, which is approximately
+/// `MIN_BASE_FEE_PER_BLOB_GAS * e^(excess_blob_gas / BLOB_BASE_FEE_UPDATE_FRACTION)` (EIP-4844)
+pub fn blob_base_fee(excess_blob_gas: U256) -> U256 {
+    let factor = U256::from(MIN_BASE_FEE_PER_BLOB_GAS);
+    let denominator = U256::from(BLOB_BASE_FEE_UPDATE_FRACTION);
+
+    // Taylor expansion of `factor * e^(excess_blob_gas / denominator)`
+    let mut i = U256::one();
+    let mut output = U256::zero();
+    let mut numerator_accumulator = factor * denominator;
+    while !numerator_accumulator.is_zero() {
+        output += numerator_accumulator;
+        numerator_accumulator = numerator_accumulator * excess_blob_gas / (denominator * i);
+        i += U256::one();
+    }
+
+    output / denominator
+}
+
+/// Subtracts `amount` from the available gas, failing with an out of gas
+/// error if there is not enough of it
+pub fn charge_gas(ctx: &mut ExecutionContext, amount: impl Into<U256>) -> Result<(), EVMError> {
+    let amount = amount.into();
+
+    if ctx.machine_state.gas < amount {
+        return Err(EVMError::OutOfGasError(ctx.machine_state.pc));
+    }
+    ctx.machine_state.gas -= amount;
+
+    Ok(())
+}
+
+/// All but one 64th of the available gas, which is the maximum amount
+/// of gas a sub-context can receive (EIP-150)
+pub fn all_but_one_64th(gas: U256) -> U256 {
+    gas - gas / 64
+}
+
+/// Models the access cost function A in the yellow paper (EIP-2929):
+/// marks `address` as accessed and returns the cost of the access,
+/// which is cheaper if the account was already accessed during the transaction.
+/// Before Berlin, the access is priced by `static_cost` instead.
+/// As every access to another account goes through here,
+/// this is also where the account is read from the database
+pub fn account_access_cost(ctx: &mut ExecutionContext, address: U256) -> u64 {
+    ctx.load_account(address);
+    let cold = ctx.warm_account(address);
+
+    if !ctx.spec.is_enabled(SpecId::Berlin) {
+        G_ZERO
+    } else if cold {
+        G_COLDACCOUNTACCESS
+    } else {
+        G_WARMACCESS
+    }
+}
+
+/// Like `account_access_cost`, but for the storage slot `key` of `address`
+pub fn storage_access_cost(ctx: &mut ExecutionContext, address: U256, key: U256) -> u64 {
+    ctx.load_storage(address, key);
+    let cold = ctx.warm_storage(address, key);
+
+    if !ctx.spec.is_enabled(SpecId::Berlin) {
+        G_ZERO
+    } else if cold {
+        G_COLDSLOAD
+    } else {
+        G_WARMACCESS
+    }
+}
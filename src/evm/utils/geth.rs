@@ -0,0 +1,215 @@
+// The formats geth uses for the world state: the `alloc` section of a
+// genesis.json file, which sets up the accounts of the genesis block, and
+// the dump returned by `debug_dumpBlock`, to exchange state with other tools
+
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+use primitive_types::U256;
+use serde::{de, Deserialize, Deserializer, Serialize};
+
+use super::{
+    constants::KECCAK_EMPTY,
+    helpers::keccak256,
+    snapshot::{Bytes, Word},
+    trie::{state_root, storage_root},
+    types::{AccountState, ExecutionContext},
+};
+
+/// A genesis.json file, of which only the allocation of the accounts is read
+#[derive(Debug, Clone, Deserialize)]
+pub struct Genesis {
+    pub alloc: GenesisAlloc,
+}
+
+/// The accounts of the genesis block, indexed by address,
+/// which may be written with or without the `0x` prefix
+pub type GenesisAlloc = BTreeMap<Word, GenesisAccount>;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GenesisAccount {
+    #[serde(deserialize_with = "hex_or_decimal")]
+    pub balance: U256,
+
+    #[serde(default, deserialize_with = "hex_or_decimal_usize")]
+    pub nonce: usize,
+
+    #[serde(default)]
+    pub code: Bytes,
+
+    #[serde(default)]
+    pub storage: BTreeMap<Word, Word>,
+}
+
+impl Genesis {
+    pub fn from_json(json: &str) -> io::Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn load_json(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    /// Replaces the world state of `ctx` with the allocated accounts,
+    /// together with their code
+    pub fn load(&self, ctx: &mut ExecutionContext) {
+        ctx.clear_world_state();
+        for (address, account) in &self.alloc {
+            ctx.insert_account(
+                address.0,
+                AccountState::from(account),
+                account.code.0.clone(),
+            );
+        }
+    }
+}
+
+impl From<&GenesisAccount> for AccountState {
+    fn from(account: &GenesisAccount) -> Self {
+        AccountState {
+            nonce: account.nonce,
+            balance: account.balance,
+            code_hash: KECCAK_EMPTY,
+            storage: account
+                .storage
+                .iter()
+                .filter(|(_, value)| !value.0.is_zero())
+                .map(|(key, value)| (key.0, value.0))
+                .collect(),
+        }
+    }
+}
+
+/// A dump of the world state in the format of `debug_dumpBlock`
+#[derive(Debug, Clone, Serialize)]
+pub struct StateDump {
+    pub root: String,
+
+    /// the accounts, indexed by their checksummed address (EIP-55)
+    pub accounts: BTreeMap<String, DumpAccount>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DumpAccount {
+    /// the balance in wei, in decimal
+    pub balance: String,
+    pub nonce: usize,
+
+    /// the root of the storage trie
+    pub root: String,
+    pub code_hash: String,
+
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub code: String,
+
+    /// the storage slots whose value is not zero. As in geth, the keys are
+    /// 32 bytes long while the values have neither leading zeros nor prefix
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub storage: BTreeMap<String, String>,
+}
+
+impl StateDump {
+    /// Dumps the world state of `ctx`, or returns `None`
+    /// if its database cannot list its accounts
+    pub fn new(ctx: &ExecutionContext) -> Option<Self> {
+        let world_state = ctx.world_state()?;
+        let accounts = world_state
+            .iter()
+            .map(|(address, account_state)| {
+                let code = ctx.code_by_hash(account_state.code_hash);
+                let dump_account = DumpAccount {
+                    balance: account_state.balance.to_string(),
+                    nonce: account_state.nonce,
+                    root: hash_to_hex(storage_root(&account_state.storage)),
+                    code_hash: hash_to_hex(account_state.code_hash),
+                    code: if code.is_empty() {
+                        String::new()
+                    } else {
+                        format!("0x{}", hex::encode(code))
+                    },
+                    storage: account_state
+                        .storage
+                        .iter()
+                        .filter(|(_, value)| !value.is_zero())
+                        .map(|(key, value)| (hash_to_hex(*key), format!("{:x}", value)))
+                        .map(|(key, value)| {
+                            // the value is made of whole bytes
+                            let padding = if value.len() % 2 == 1 { "0" } else { "" };
+                            (key, format!("{}{}", padding, value))
+                        })
+                        .collect(),
+                };
+                (checksum_address(*address), dump_account)
+            })
+            .collect();
+
+        Some(StateDump {
+            root: hash_to_hex(state_root(&world_state)),
+            accounts,
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn save_json(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_json())
+    }
+}
+
+fn hash_to_hex(hash: U256) -> String {
+    format!("{:#066x}", hash)
+}
+
+/// Returns the address in hexadecimal with the mixed-case checksum of EIP-55:
+/// a letter is uppercase if the matching nibble of the Keccak-256 hash of
+/// the lowercase address is at least 8
+fn checksum_address(address: U256) -> String {
+    let lowercase = format!("{:040x}", address);
+    let hash = keccak256(lowercase.as_bytes());
+
+    let checksummed: String = lowercase
+        .chars()
+        .enumerate()
+        .map(|(i, char)| {
+            let nibble = (hash.byte(31 - i / 2) >> (4 * (1 - i % 2))) & 0x0f;
+            if nibble >= 8 {
+                char.to_ascii_uppercase()
+            } else {
+                char
+            }
+        })
+        .collect();
+
+    format!("0x{}", checksummed)
+}
+
+/// A quantity of geth, which is either a JSON number or a
+/// string holding a hexadecimal or decimal number
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Quantity {
+    Number(u64),
+    Text(String),
+}
+
+fn hex_or_decimal<'de, D: Deserializer<'de>>(deserializer: D) -> Result<U256, D::Error> {
+    let str = match Quantity::deserialize(deserializer)? {
+        Quantity::Number(number) => return Ok(U256::from(number)),
+        Quantity::Text(str) => str,
+    };
+
+    let value = match str.strip_prefix("0x") {
+        Some(digits) => U256::from_str_radix(digits, 16).ok(),
+        None => U256::from_dec_str(&str).ok(),
+    };
+    value.ok_or_else(|| de::Error::custom(format!("invalid quantity {}", str)))
+}
+
+fn hex_or_decimal_usize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<usize, D::Error> {
+    let value = hex_or_decimal(deserializer)?;
+    value
+        .try_into()
+        .map_err(|_| de::Error::custom(format!("quantity {} too large", value)))
+}
@@ -1,33 +1,59 @@
 use std::collections::HashMap;
 
 use primitive_types::U256;
+use sha3::{Digest, Keccak256};
 
 use crate::evm::opcodes;
 
 use super::{
     constants::{JUMPDEST, PUSH_1, PUSH_32},
     errors::EVMError,
-    types::{ExecutionContext, GlobalState, OpcodeResult, Opcodes},
+    gas::{charge_gas, memory_cost},
+    rlp,
+    spec::{opcode_introduced_in, SpecId},
+    types::{ExecutionContext, GlobalState, Log, OpcodeResult, Opcodes},
 };
 
 /// Models the EMPTY function in the yellow paper
-pub fn is_account_empty(state: GlobalState, address: U256) -> bool {
+pub fn is_account_empty(state: &GlobalState, address: U256) -> bool {
     if let Some(account_state) = state.get(&address) {
-        account_state.nonce == 0 && account_state.balance.is_zero()
+        !account_state.has_code() && account_state.nonce == 0 && account_state.balance.is_zero()
     } else {
         false
     }
 }
 
 /// Models the DEAD function in the yellow paper
-pub fn is_account_dead(state: GlobalState, address: U256) -> bool {
+pub fn is_account_dead(state: &GlobalState, address: U256) -> bool {
     state.get(&address).is_none() || is_account_empty(state, address)
 }
 
+/// Moves `value` wei from the account at `from` to the one at `to`, which is
+/// created if it does not exist, and marks both of them as touched.
+/// Returns `false` and leaves the state as it is if `from` cannot afford it
+pub fn transfer(ctx: &mut ExecutionContext, from: U256, to: U256, value: U256) -> bool {
+    let balance = ctx
+        .global_state
+        .get(&from)
+        .map(|account_state| account_state.balance)
+        .unwrap_or(U256::zero());
+    if balance < value {
+        return false;
+    }
+
+    ctx.account_mut(from).balance -= value;
+    ctx.account_mut(to).balance += value;
+
+    ctx.touch_account(from);
+    ctx.touch_account(to);
+
+    true
+}
+
 /// Returns a vector of length `n` in which all elements are indeed present
 pub fn pop_n(ctx: &mut ExecutionContext, n: usize) -> Result<Vec<U256>, EVMError> {
     if ctx.machine_state.stack.len() < n {
-        return Err(EVMError::EmptyStackError(ctx.clone()));
+        return Err(EVMError::EmptyStackError);
     }
 
     let mut result = Vec::with_capacity(n);
@@ -87,13 +113,126 @@ pub fn hex_string_from_byte(byte: u8) -> String {
     }
 }
 
-/// idx is in bytes
-pub fn update_active_words_memory(ctx: &mut ExecutionContext, last_accessed_memory_idx: usize) {
-    let last_accessed_word_idx = last_accessed_memory_idx / 32;
-    ctx.machine_state.active_words_memory = usize::max(
-        ctx.machine_state.active_words_memory,
-        last_accessed_word_idx + 1,
-    );
+/// Converts the memory range of `size` bytes starting from `offset` to
+/// usize. An empty range is moved to zero, since it never touches memory
+/// wherever it starts, while a range that does not fit cannot be paid for
+pub fn memory_range(
+    ctx: &ExecutionContext,
+    offset: U256,
+    size: U256,
+) -> Result<(usize, usize), EVMError> {
+    if size.is_zero() {
+        return Ok((0, 0));
+    }
+
+    match (usize::try_from(offset), usize::try_from(size)) {
+        (Ok(offset), Ok(size)) => Ok((offset, size)),
+        _ => Err(EVMError::OutOfGasError(ctx.machine_state.pc)),
+    }
+}
+
+/// Models the memory expansion function M in the yellow paper. It charges
+/// the gas for the newly active words when accessing `size` bytes starting
+/// from `offset`, and makes sure that memory is large enough to hold them
+pub fn expand_memory(
+    ctx: &mut ExecutionContext,
+    offset: usize,
+    size: usize,
+) -> Result<(), EVMError> {
+    if size == 0 {
+        return Ok(());
+    }
+
+    let end = offset
+        .checked_add(size)
+        .ok_or(EVMError::OutOfGasError(ctx.machine_state.pc))?;
+    let active_words = end.div_ceil(32);
+
+    if active_words > ctx.machine_state.active_words_memory {
+        let cost = memory_cost(active_words) - memory_cost(ctx.machine_state.active_words_memory);
+        charge_gas(ctx, cost)?;
+        ctx.machine_state.active_words_memory = active_words;
+    }
+
+    if ctx.machine_state.memory.len() < active_words * 32 {
+        ctx.machine_state.memory.resize(active_words * 32, 0);
+    }
+
+    Ok(())
+}
+
+/// Returns `size` bytes of `data` starting from `offset`, which may be any
+/// word, where the bytes past the end of `data` are read as zeros
+pub fn read_padded(data: &[u8], offset: U256, size: usize) -> Vec<u8> {
+    let mut bytes = vec![0_u8; size];
+
+    if let Ok(offset) = usize::try_from(offset) {
+        if offset < data.len() {
+            let end = data.len().min(offset.saturating_add(size));
+            bytes[..end - offset].copy_from_slice(&data[offset..end]);
+        }
+    }
+
+    bytes
+}
+
+/// Returns the Keccak-256 hash of `data` as a scalar
+pub fn keccak256(data: &[u8]) -> U256 {
+    let mut hasher = Keccak256::new();
+    hasher.update(data);
+
+    U256::from(hasher.finalize().as_slice())
+}
+
+/// Returns the Keccak-256 hash of the RLP encoding of `logs`,
+/// each of them encoded as `[address, [topics], data]`
+pub fn logs_hash(logs: &[Log]) -> U256 {
+    let encoded_logs: Vec<Vec<u8>> = logs
+        .iter()
+        .map(|log| {
+            let topics: Vec<Vec<u8>> = log.topics.iter().map(|t| rlp::encode_hash(*t)).collect();
+            rlp::encode_list(&[
+                rlp::encode_address(log.address),
+                rlp::encode_list(&topics),
+                rlp::encode_bytes(&log.data),
+            ])
+        })
+        .collect();
+
+    keccak256(&rlp::encode_list(&encoded_logs))
+}
+
+/// Returns the address of the contract created by `sender` when its nonce
+/// is `nonce`, that is the rightmost 160 bits of `keccak256(rlp([sender, nonce]))`
+pub fn create_address(sender: U256, nonce: usize) -> U256 {
+    let encoded = rlp::encode_list(&[
+        rlp::encode_address(sender),
+        rlp::encode_u256(U256::from(nonce)),
+    ]);
+
+    modulo_address_size(&keccak256(&encoded))
+}
+
+/// Returns the address of the contract created with CREATE2 by `sender`,
+/// that is the rightmost 160 bits of
+/// `keccak256(0xff ++ sender ++ salt ++ keccak256(initialisation_code))` (EIP-1014)
+pub fn create2_address(sender: U256, salt: U256, initialisation_code: &[u8]) -> U256 {
+    let mut sender_bytes = [0_u8; 32];
+    sender.to_big_endian(&mut sender_bytes);
+    let mut salt_bytes = [0_u8; 32];
+    salt.to_big_endian(&mut salt_bytes);
+    let mut code_hash_bytes = [0_u8; 32];
+    keccak256(initialisation_code).to_big_endian(&mut code_hash_bytes);
+
+    let to_hash = [
+        &[0xff_u8][..],
+        &sender_bytes[12..],
+        &salt_bytes,
+        &code_hash_bytes,
+    ]
+    .concat();
+
+    modulo_address_size(&keccak256(&to_hash))
 }
 
 /// Returns a new U256 calculated as `val.mod(2^160)`
@@ -146,7 +285,8 @@ pub fn get_jumpdests(ctx: &mut ExecutionContext) -> Vec<u8> {
     jumpdests
 }
 
-pub fn get_opcodes() -> Opcodes {
+/// Returns the opcodes available under `spec`, indexed by their byte
+pub fn get_opcodes(spec: SpecId) -> Opcodes {
     let mut opcodes: Opcodes = HashMap::new();
 
     opcodes.insert(0x00, Box::new(opcodes::stop_and_arithmetic::stop));
@@ -203,6 +343,8 @@ pub fn get_opcodes() -> Opcodes {
     opcodes.insert(0x46, Box::new(opcodes::block::chain));
     opcodes.insert(0x47, Box::new(opcodes::block::selfbalance));
     opcodes.insert(0x48, Box::new(opcodes::block::basefee));
+    opcodes.insert(0x49, Box::new(opcodes::block::blobhash));
+    opcodes.insert(0x4a, Box::new(opcodes::block::blobbasefee));
 
     opcodes.insert(0x50, Box::new(opcodes::stack_memory_storage_flow::pop));
     opcodes.insert(0x51, Box::new(opcodes::stack_memory_storage_flow::mload));
@@ -216,6 +358,9 @@ pub fn get_opcodes() -> Opcodes {
     opcodes.insert(0x59, Box::new(opcodes::stack_memory_storage_flow::msize));
     opcodes.insert(0x5a, Box::new(opcodes::stack_memory_storage_flow::gas));
     opcodes.insert(0x5b, Box::new(opcodes::stack_memory_storage_flow::jumpdest));
+    opcodes.insert(0x5c, Box::new(opcodes::stack_memory_storage_flow::tload));
+    opcodes.insert(0x5d, Box::new(opcodes::stack_memory_storage_flow::tstore));
+    opcodes.insert(0x5e, Box::new(opcodes::stack_memory_storage_flow::mcopy));
 
     opcodes.insert(0x5f, Box::new(opcodes::push::push));
     opcodes.insert(0x60, Box::new(opcodes::push::push));
@@ -287,8 +432,10 @@ pub fn get_opcodes() -> Opcodes {
 
     opcodes.insert(0xf0, Box::new(opcodes::system::create));
     opcodes.insert(0xf1, Box::new(opcodes::system::call));
+    opcodes.insert(0xf2, Box::new(opcodes::system::callcode));
     opcodes.insert(0xf3, Box::new(opcodes::system::r#return));
     opcodes.insert(0xf4, Box::new(opcodes::system::delegatecall));
+    opcodes.insert(0xf5, Box::new(opcodes::system::create2));
     opcodes.insert(0xfa, Box::new(opcodes::system::staticcall));
     opcodes.insert(0xfd, Box::new(opcodes::system::revert));
     opcodes.insert(0xfe, Box::new(opcodes::system::invalid));
@@ -300,5 +447,7 @@ pub fn get_opcodes() -> Opcodes {
     opcodes.insert(0xa3, Box::new(opcodes::logging::log));
     opcodes.insert(0xa4, Box::new(opcodes::logging::log));
 
+    opcodes.retain(|opcode, _| spec.is_enabled(opcode_introduced_in(*opcode)));
+
     opcodes
 }
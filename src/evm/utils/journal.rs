@@ -0,0 +1,260 @@
+// The journal records how every change to the state made during a transaction
+// can be undone, so that reverting a sub-context only touches what it changed
+// instead of restoring a copy of the whole state taken before running it
+
+use primitive_types::U256;
+
+use super::types::{AccountState, ExecutionContext, Log};
+
+/// A change to the global state or to the accrued substate,
+/// holding what is needed to undo it
+#[derive(Debug, Clone)]
+pub enum JournalEntry {
+    /// the nonce, balance or code hash of the account changed. It held the
+    /// given values before, or did not exist if they are missing
+    AccountChanged {
+        address: U256,
+        previous: Option<(usize, U256, U256)>,
+    },
+
+    /// the account was replaced by a new one with empty storage
+    AccountCreated {
+        address: U256,
+        previous: Option<AccountState>,
+    },
+
+    StorageChanged {
+        address: U256,
+        key: U256,
+        previous: Option<U256>,
+    },
+
+    TransientStorageChanged {
+        address: U256,
+        key: U256,
+        previous: Option<U256>,
+    },
+
+    RefundChanged(U256),
+    LogAdded,
+    AccountWarmed(U256),
+    StorageWarmed(U256, U256),
+    AccountTouched(U256),
+    SelfDestructed(U256),
+
+    /// the account was added to `AccruedSubstate::created_accounts`
+    CreatedAccountMarked(U256),
+
+    /// the account was added to `LoadedState::cleared_storage`
+    StorageCleared(U256),
+}
+
+/// The length of the journal when the checkpoint was taken: the
+/// changes recorded after it are the ones to undo to go back to it
+#[derive(Debug, Clone, Copy)]
+pub struct Checkpoint {
+    journal_len: usize,
+}
+
+impl ExecutionContext {
+    /// Marks the point the changes made from now on can be reverted to
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            journal_len: self.journal.len(),
+        }
+    }
+
+    /// Discards every change made to the global state and to the
+    /// accrued substate after `checkpoint` was taken
+    pub fn revert_to(&mut self, checkpoint: Checkpoint) {
+        let entries = self.journal.split_off(checkpoint.journal_len);
+        entries.into_iter().rev().for_each(|entry| self.undo(entry));
+    }
+
+    fn undo(&mut self, entry: JournalEntry) {
+        let substate = &mut self.accrued_substate;
+
+        match entry {
+            JournalEntry::AccountChanged { address, previous } => match previous {
+                Some((nonce, balance, code_hash)) => {
+                    if let Some(account_state) = self.global_state.get_mut(&address) {
+                        account_state.nonce = nonce;
+                        account_state.balance = balance;
+                        account_state.code_hash = code_hash;
+                    }
+                }
+                None => {
+                    self.global_state.remove(&address);
+                }
+            },
+            JournalEntry::AccountCreated { address, previous } => match previous {
+                Some(account_state) => {
+                    self.global_state.insert(address, account_state);
+                }
+                None => {
+                    self.global_state.remove(&address);
+                }
+            },
+            JournalEntry::StorageChanged {
+                address,
+                key,
+                previous,
+            } => {
+                if let Some(account_state) = self.global_state.get_mut(&address) {
+                    match previous {
+                        Some(value) => account_state.storage.insert(key, value),
+                        None => account_state.storage.remove(&key),
+                    };
+                }
+            }
+            JournalEntry::TransientStorageChanged {
+                address,
+                key,
+                previous,
+            } => {
+                match previous {
+                    Some(value) => substate.transient_storage.insert((address, key), value),
+                    None => substate.transient_storage.remove(&(address, key)),
+                };
+            }
+            JournalEntry::RefundChanged(previous) => substate.refund_balance = previous,
+            JournalEntry::LogAdded => {
+                substate.logs.pop();
+            }
+            JournalEntry::AccountWarmed(address) => {
+                substate.accessed_accounts.remove(&address);
+            }
+            JournalEntry::StorageWarmed(address, key) => {
+                substate.accessed_storage_keys.remove(&(address, key));
+            }
+            JournalEntry::AccountTouched(address) => {
+                substate.touched_accounts.remove(&address);
+            }
+            JournalEntry::SelfDestructed(address) => {
+                substate.self_destruct_set.remove(&address);
+            }
+            JournalEntry::CreatedAccountMarked(address) => {
+                substate.created_accounts.remove(&address);
+            }
+            JournalEntry::StorageCleared(address) => {
+                self.loaded.cleared_storage.remove(&address);
+            }
+        }
+    }
+
+    /// Returns the account at `address` to change its nonce, balance or code
+    /// hash, reading it from the database or creating it if it does not exist.
+    /// Its storage is left out of the journal entry, so it has to be changed
+    /// with `set_storage` instead
+    pub fn account_mut(&mut self, address: U256) -> &mut AccountState {
+        self.load_account(address);
+
+        let previous = self.global_state.get(&address).map(|account_state| {
+            (
+                account_state.nonce,
+                account_state.balance,
+                account_state.code_hash,
+            )
+        });
+        self.journal
+            .push(JournalEntry::AccountChanged { address, previous });
+
+        self.global_state
+            .entry(address)
+            .or_insert_with(AccountState::new)
+    }
+
+    /// Replaces the account at `address` with `account_state` as the
+    /// creation of a contract does, so that its storage in the database
+    /// is no longer read, and marks it as created in this transaction
+    pub fn create_account(&mut self, address: U256, account_state: AccountState) {
+        let previous = self.global_state.insert(address, account_state);
+        self.journal
+            .push(JournalEntry::AccountCreated { address, previous });
+
+        if self.accrued_substate.created_accounts.insert(address) {
+            self.journal
+                .push(JournalEntry::CreatedAccountMarked(address));
+        }
+        if self.loaded.cleared_storage.insert(address) {
+            self.journal.push(JournalEntry::StorageCleared(address));
+        }
+    }
+
+    /// Sets the storage slot `key` of the account at `address`,
+    /// which is created if it does not exist
+    pub fn set_storage(&mut self, address: U256, key: U256, value: U256) {
+        if !self.global_state.contains_key(&address) {
+            self.account_mut(address);
+        }
+
+        let previous = self
+            .global_state
+            .get_mut(&address)
+            .and_then(|account_state| account_state.storage.insert(key, value));
+        self.journal.push(JournalEntry::StorageChanged {
+            address,
+            key,
+            previous,
+        });
+    }
+
+    pub fn set_transient_storage(&mut self, address: U256, key: U256, value: U256) {
+        let previous = self
+            .accrued_substate
+            .transient_storage
+            .insert((address, key), value);
+        self.journal.push(JournalEntry::TransientStorageChanged {
+            address,
+            key,
+            previous,
+        });
+    }
+
+    /// Returns the refund balance to change it
+    pub fn refund_balance_mut(&mut self) -> &mut U256 {
+        self.journal.push(JournalEntry::RefundChanged(
+            self.accrued_substate.refund_balance,
+        ));
+        &mut self.accrued_substate.refund_balance
+    }
+
+    pub fn add_log(&mut self, log: Log) {
+        self.accrued_substate.logs.push(log);
+        self.journal.push(JournalEntry::LogAdded);
+    }
+
+    /// Marks the account at `address` as accessed,
+    /// returning whether it was not accessed before
+    pub fn warm_account(&mut self, address: U256) -> bool {
+        let cold = self.accrued_substate.accessed_accounts.insert(address);
+        if cold {
+            self.journal.push(JournalEntry::AccountWarmed(address));
+        }
+        cold
+    }
+
+    /// Like `warm_account`, but for the storage slot `key` of `address`
+    pub fn warm_storage(&mut self, address: U256, key: U256) -> bool {
+        let cold = self
+            .accrued_substate
+            .accessed_storage_keys
+            .insert((address, key));
+        if cold {
+            self.journal.push(JournalEntry::StorageWarmed(address, key));
+        }
+        cold
+    }
+
+    pub fn touch_account(&mut self, address: U256) {
+        if self.accrued_substate.touched_accounts.insert(address) {
+            self.journal.push(JournalEntry::AccountTouched(address));
+        }
+    }
+
+    pub fn self_destruct(&mut self, address: U256) {
+        if self.accrued_substate.self_destruct_set.insert(address) {
+            self.journal.push(JournalEntry::SelfDestructed(address));
+        }
+    }
+}
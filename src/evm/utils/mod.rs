@@ -1,6 +1,16 @@
 pub mod constants;
+pub mod database;
 pub mod errors;
+pub mod gas;
+pub mod geth;
 pub mod helpers;
+pub mod journal;
+pub mod rlp;
+pub mod snapshot;
+pub mod spec;
+pub mod state_test_types;
 pub mod test_types;
 pub mod traits;
+pub mod transaction;
+pub mod trie;
 pub mod types;
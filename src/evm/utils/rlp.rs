@@ -0,0 +1,62 @@
+// Recursive Length Prefix serialisation, as defined in Appendix B of the
+// yellow paper. Only encoding is needed by the EVM
+
+use primitive_types::U256;
+
+/// Encodes a byte array
+pub fn encode_bytes(bytes: &[u8]) -> Vec<u8> {
+    if bytes.len() == 1 && bytes[0] < 0x80 {
+        return bytes.to_vec();
+    }
+
+    [encode_length(bytes.len(), 0x80), bytes.to_vec()].concat()
+}
+
+/// Encodes a scalar as its big-endian representation without leading zeros,
+/// so that zero is encoded as the empty byte array
+pub fn encode_u256(value: U256) -> Vec<u8> {
+    encode_bytes(&u256_to_minimal_bytes(value))
+}
+
+/// Encodes a 160-bit address as a byte array of length 20
+pub fn encode_address(address: U256) -> Vec<u8> {
+    let mut bytes = [0_u8; 32];
+    address.to_big_endian(&mut bytes);
+
+    encode_bytes(&bytes[12..])
+}
+
+/// Encodes a 256-bit hash as a byte array of length 32
+pub fn encode_hash(hash: U256) -> Vec<u8> {
+    let mut bytes = [0_u8; 32];
+    hash.to_big_endian(&mut bytes);
+
+    encode_bytes(&bytes)
+}
+
+/// Encodes a list whose items are already encoded
+pub fn encode_list(items: &[Vec<u8>]) -> Vec<u8> {
+    let payload = items.concat();
+
+    [encode_length(payload.len(), 0xc0), payload].concat()
+}
+
+/// Returns the prefix of an item, either a byte array (with `offset` 0x80)
+/// or a list (with `offset` 0xc0), whose payload is `length` bytes long
+fn encode_length(length: usize, offset: u8) -> Vec<u8> {
+    if length < 56 {
+        vec![offset + length as u8]
+    } else {
+        let length_bytes = u256_to_minimal_bytes(U256::from(length));
+        [vec![offset + 55 + length_bytes.len() as u8], length_bytes].concat()
+    }
+}
+
+/// Big-endian representation of `value` without leading zeros
+fn u256_to_minimal_bytes(value: U256) -> Vec<u8> {
+    let mut bytes = [0_u8; 32];
+    value.to_big_endian(&mut bytes);
+
+    let leading_zeros = bytes.iter().take_while(|byte| **byte == 0).count();
+    bytes[leading_zeros..].to_vec()
+}
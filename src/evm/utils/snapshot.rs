@@ -0,0 +1,239 @@
+// Snapshots of the world state which can be saved to a file and loaded back,
+// either as JSON, to be read by humans, or in the compact binary encoding
+// of bincode, for large states
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, ErrorKind},
+    path::Path,
+    sync::Arc,
+};
+
+use bincode::Options;
+use primitive_types::U256;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{
+    constants::KECCAK_EMPTY,
+    database::InMemoryDatabase,
+    types::{AccountState, CodeStore, ExecutionContext, GlobalState},
+};
+
+/// The accounts of the world state, together with their code and storage
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateSnapshot {
+    pub accounts: BTreeMap<Word, AccountSnapshot>,
+
+    /// the code of the accounts, indexed by its hash
+    pub code: BTreeMap<Word, Bytes>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountSnapshot {
+    pub nonce: usize,
+    pub balance: Word,
+    pub code_hash: Word,
+
+    /// the storage slots whose value is not zero
+    pub storage: BTreeMap<Word, Word>,
+}
+
+impl StateSnapshot {
+    /// Takes a snapshot of the world state of `ctx`, or returns `None`
+    /// if its database cannot list its accounts
+    pub fn new(ctx: &ExecutionContext) -> Option<Self> {
+        let mut snapshot = StateSnapshot::default();
+
+        for (address, account_state) in &ctx.world_state()? {
+            snapshot.accounts.insert(
+                Word(*address),
+                AccountSnapshot {
+                    nonce: account_state.nonce,
+                    balance: Word(account_state.balance),
+                    code_hash: Word(account_state.code_hash),
+                    storage: account_state
+                        .storage
+                        .iter()
+                        .filter(|(_, value)| !value.is_zero())
+                        .map(|(key, value)| (Word(*key), Word(*value)))
+                        .collect(),
+                },
+            );
+
+            if account_state.has_code() {
+                snapshot.code.insert(
+                    Word(account_state.code_hash),
+                    Bytes(ctx.code_by_hash(account_state.code_hash).to_vec()),
+                );
+            }
+        }
+
+        Some(snapshot)
+    }
+
+    /// Replaces the world state of `ctx` with the one of the snapshot. It fails,
+    /// leaving `ctx` untouched, if the code of an account is missing
+    pub fn load(&self, ctx: &mut ExecutionContext) -> io::Result<()> {
+        let missing_code = self.accounts.iter().find(|(_, account)| {
+            account.code_hash.0 != KECCAK_EMPTY && !self.code.contains_key(&account.code_hash)
+        });
+        if let Some((address, _)) = missing_code {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("missing code of the account {:#x}", address.0),
+            ));
+        }
+
+        ctx.clear_world_state();
+        for (address, account) in &self.accounts {
+            let code = self
+                .code
+                .get(&account.code_hash)
+                .map(|code| code.0.clone())
+                .unwrap_or_default();
+            ctx.insert_account(address.0, AccountState::from(account), code);
+        }
+
+        Ok(())
+    }
+
+    pub fn global_state(&self) -> GlobalState {
+        self.accounts
+            .iter()
+            .map(|(address, account)| (address.0, AccountState::from(account)))
+            .collect()
+    }
+
+    pub fn code_store(&self) -> CodeStore {
+        self.code
+            .iter()
+            .map(|(code_hash, code)| (code_hash.0, Arc::from(code.0.as_slice())))
+            .collect()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn from_json(json: &str) -> io::Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_binary(&self) -> Vec<u8> {
+        bincode::DefaultOptions::new().serialize(self).unwrap()
+    }
+
+    pub fn from_binary(bytes: &[u8]) -> io::Result<Self> {
+        bincode::DefaultOptions::new()
+            .deserialize(bytes)
+            .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+    }
+
+    pub fn save_json(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_json())
+    }
+
+    pub fn load_json(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    pub fn save_binary(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_binary())
+    }
+
+    pub fn load_binary(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_binary(&fs::read(path)?)
+    }
+}
+
+impl From<&AccountSnapshot> for AccountState {
+    fn from(account: &AccountSnapshot) -> Self {
+        AccountState {
+            nonce: account.nonce,
+            balance: account.balance.0,
+            code_hash: account.code_hash.0,
+            storage: account
+                .storage
+                .iter()
+                .map(|(key, value)| (key.0, value.0))
+                .collect(),
+        }
+    }
+}
+
+/// A snapshot can be used as the database the execution starts from,
+/// so that its accounts are only read when they are accessed
+impl From<&StateSnapshot> for InMemoryDatabase {
+    fn from(snapshot: &StateSnapshot) -> Self {
+        InMemoryDatabase {
+            accounts: snapshot.global_state(),
+            code_store: snapshot.code_store(),
+            ..Default::default()
+        }
+    }
+}
+
+/// A 256-bit word, written as a hexadecimal string in JSON and
+/// as its big-endian bytes without the leading zeros in binary
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Word(pub U256);
+
+impl Serialize for Word {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&format!("{:#x}", self.0))
+        } else {
+            let mut bytes = [0_u8; 32];
+            self.0.to_big_endian(&mut bytes);
+            let leading_zeros = bytes.iter().take_while(|byte| **byte == 0).count();
+            bytes[leading_zeros..].to_vec().serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Word {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let str = String::deserialize(deserializer)?;
+            let digits = str.strip_prefix("0x").unwrap_or(&str);
+            U256::from_str_radix(digits, 16)
+                .map(Word)
+                .map_err(|_| de::Error::custom(format!("invalid word {}", str)))
+        } else {
+            let bytes = Vec::<u8>::deserialize(deserializer)?;
+            if bytes.len() > 32 {
+                return Err(de::Error::invalid_length(bytes.len(), &"at most 32 bytes"));
+            }
+            Ok(Word(U256::from_big_endian(&bytes)))
+        }
+    }
+}
+
+/// A byte array, written as a hexadecimal string in JSON
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Bytes(pub Vec<u8>);
+
+impl Serialize for Bytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&format!("0x{}", hex::encode(&self.0)))
+        } else {
+            self.0.serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Bytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let str = String::deserialize(deserializer)?;
+            let digits = str.strip_prefix("0x").unwrap_or(&str);
+            hex::decode(digits)
+                .map(Bytes)
+                .map_err(|_| de::Error::custom(format!("invalid bytes {}", str)))
+        } else {
+            Vec::<u8>::deserialize(deserializer).map(Bytes)
+        }
+    }
+}
@@ -0,0 +1,71 @@
+// The hard forks of the Ethereum mainnet which changed the rules of the EVM
+
+/// A set of rules of the EVM, named after the hard fork which introduced it.
+/// Constantinople is left out as it was immediately replaced by Petersburg,
+/// which reverted its net gas metering for SSTORE (EIP-1283).
+/// The forks after Cancun, starting from Prague, are not supported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum SpecId {
+    Frontier,
+    Homestead,
+    TangerineWhistle,
+    SpuriousDragon,
+    Byzantium,
+    Petersburg,
+    Istanbul,
+    Berlin,
+    London,
+    Paris,
+    Shanghai,
+    #[default]
+    Cancun,
+}
+
+impl SpecId {
+    /// Whether the rules introduced by `fork` apply under this spec
+    pub fn is_enabled(self, fork: SpecId) -> bool {
+        self >= fork
+    }
+
+    /// Returns the spec of a hard fork named as in ethereum/tests,
+    /// if it is supported
+    pub fn from_fork_name(name: &str) -> Option<SpecId> {
+        match name {
+            "Frontier" => Some(SpecId::Frontier),
+            "Homestead" => Some(SpecId::Homestead),
+            "EIP150" => Some(SpecId::TangerineWhistle),
+            "EIP158" => Some(SpecId::SpuriousDragon),
+            "Byzantium" => Some(SpecId::Byzantium),
+            "ConstantinopleFix" | "Petersburg" => Some(SpecId::Petersburg),
+            "Istanbul" => Some(SpecId::Istanbul),
+            "Berlin" => Some(SpecId::Berlin),
+            "London" => Some(SpecId::London),
+            "Merge" | "Paris" => Some(SpecId::Paris),
+            "Shanghai" => Some(SpecId::Shanghai),
+            "Cancun" => Some(SpecId::Cancun),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the hard fork which introduced `opcode`
+pub fn opcode_introduced_in(opcode: u8) -> SpecId {
+    match opcode {
+        // DELEGATECALL (EIP-7)
+        0xf4 => SpecId::Homestead,
+        // RETURNDATASIZE, RETURNDATACOPY (EIP-211), STATICCALL (EIP-214), REVERT (EIP-140)
+        0x3d | 0x3e | 0xfa | 0xfd => SpecId::Byzantium,
+        // SHL, SHR, SAR (EIP-145), EXTCODEHASH (EIP-1052), CREATE2 (EIP-1014)
+        0x1b..=0x1d | 0x3f | 0xf5 => SpecId::Petersburg,
+        // CHAINID (EIP-1344), SELFBALANCE (EIP-1884)
+        0x46 | 0x47 => SpecId::Istanbul,
+        // BASEFEE (EIP-3198)
+        0x48 => SpecId::London,
+        // PUSH0 (EIP-3855)
+        0x5f => SpecId::Shanghai,
+        // BLOBHASH (EIP-4844), BLOBBASEFEE (EIP-7516),
+        // TLOAD, TSTORE (EIP-1153), MCOPY (EIP-5656)
+        0x49 | 0x4a | 0x5c..=0x5e => SpecId::Cancun,
+        _ => SpecId::Frontier,
+    }
+}
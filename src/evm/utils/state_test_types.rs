@@ -0,0 +1,295 @@
+// The format of the GeneralStateTests fixtures of ethereum/tests,
+// see https://ethereum-tests.readthedocs.io/en/latest/test_types/state_tests.html
+
+use std::collections::HashMap;
+
+use k256::ecdsa::SigningKey;
+use primitive_types::U256;
+use serde::Deserialize;
+
+use super::{
+    constants::{BLOCK_HASH_HISTORY, KECCAK_EMPTY},
+    database::InMemoryDatabase,
+    gas::blob_base_fee,
+    helpers::{bytes_from_hex_str, keccak256, modulo_address_size},
+    transaction::{Transaction, TransactionType},
+    types::{AccessList, AccountState, BlockHashes, BlockHeader},
+};
+
+/// A transaction matrix executed on top of the `pre` state,
+/// whose expected outcome is given for each hard fork in `post`
+#[derive(Debug, Deserialize)]
+pub struct StateTest {
+    pub env: StateTestEnv,
+    pub pre: HashMap<String, StateTestAccount>,
+    pub transaction: StateTestTransaction,
+    pub post: HashMap<String, Vec<PostState>>,
+}
+
+impl StateTest {
+    /// Returns the database holding the `pre` state, together with the code of
+    /// its accounts, and the hashes of the ancestors of the block of the test
+    pub fn database(&self) -> InMemoryDatabase {
+        let mut database = InMemoryDatabase {
+            block_hashes: self.env.block_hashes(),
+            ..Default::default()
+        };
+
+        for (address, account) in &self.pre {
+            let code = parse_bytes(&account.code);
+            let code_hash = keccak256(&code);
+            database.code_store.insert(code_hash, code.into());
+            database.accounts.insert(
+                parse_u256(address),
+                AccountState {
+                    code_hash,
+                    ..AccountState::from(account)
+                },
+            );
+        }
+
+        database
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateTestEnv {
+    pub current_coinbase: String,
+    pub current_difficulty: Option<String>,
+    pub current_random: Option<String>,
+    pub current_gas_limit: String,
+    pub current_number: String,
+    pub current_timestamp: String,
+    pub current_base_fee: Option<String>,
+    pub current_excess_blob_gas: Option<String>,
+}
+
+impl StateTestEnv {
+    /// The hash of a block in the state tests is the Keccak-256 hash
+    /// of the decimal representation of its number
+    pub fn block_hashes(&self) -> BlockHashes {
+        let number = parse_u256(&self.current_number);
+        let first = number.saturating_sub(U256::from(BLOCK_HASH_HISTORY));
+
+        let mut block_hashes = BlockHashes::new();
+        let mut block_number = first;
+        while block_number < number {
+            let hash = keccak256(block_number.to_string().as_bytes());
+            block_hashes.insert(block_number, hash);
+            block_number += U256::one();
+        }
+
+        block_hashes
+    }
+}
+
+impl From<&StateTestEnv> for BlockHeader {
+    fn from(env: &StateTestEnv) -> Self {
+        let mut block_header = BlockHeader::new();
+        block_header.beneficiary = parse_u256(&env.current_coinbase);
+        block_header.number = parse_u256(&env.current_number);
+        block_header.timestamp = parse_u256(&env.current_timestamp);
+        block_header.gas_limit = parse_u256(&env.current_gas_limit);
+        if let Some(d) = &env.current_difficulty {
+            block_header.difficulty = parse_u256(d);
+        }
+        if let Some(r) = &env.current_random {
+            block_header.prevrandao = parse_u256(r);
+        }
+        if let Some(f) = &env.current_base_fee {
+            block_header.base_fee = parse_u256(f);
+        }
+        if let Some(e) = &env.current_excess_blob_gas {
+            block_header.blob_base_fee = blob_base_fee(parse_u256(e));
+        }
+        block_header
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StateTestAccount {
+    pub balance: String,
+    pub code: String,
+    pub nonce: String,
+    pub storage: HashMap<String, String>,
+}
+
+impl From<&StateTestAccount> for AccountState {
+    fn from(account: &StateTestAccount) -> Self {
+        AccountState {
+            nonce: parse_usize(&account.nonce),
+            balance: parse_u256(&account.balance),
+            code_hash: KECCAK_EMPTY,
+            storage: account
+                .storage
+                .iter()
+                .map(|(key, value)| (parse_u256(key), parse_u256(value)))
+                .filter(|(_, value)| !value.is_zero())
+                .collect(),
+        }
+    }
+}
+
+/// The transactions of the matrix share all their fields,
+/// except for the data, the gas limit and the value
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateTestTransaction {
+    pub data: Vec<String>,
+    pub gas_limit: Vec<String>,
+    pub value: Vec<String>,
+    pub nonce: String,
+
+    /// the recipient, or the empty string for a contract creation
+    pub to: String,
+
+    /// the sender, which is derived from `secret_key` if missing
+    pub sender: Option<String>,
+    pub secret_key: String,
+
+    pub gas_price: Option<String>,
+    pub max_fee_per_gas: Option<String>,
+    pub max_priority_fee_per_gas: Option<String>,
+
+    /// the access list of each data, if any
+    pub access_lists: Option<Vec<Option<Vec<StateTestAccessListItem>>>>,
+
+    pub max_fee_per_blob_gas: Option<String>,
+    pub blob_versioned_hashes: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateTestAccessListItem {
+    pub address: String,
+    pub storage_keys: Vec<String>,
+}
+
+impl StateTestTransaction {
+    /// Returns the transaction of the matrix at `indexes`
+    pub fn transaction(&self, indexes: &PostStateIndexes) -> Transaction {
+        let access_list: Option<AccessList> = self
+            .access_lists
+            .as_ref()
+            .and_then(|access_lists| access_lists.get(indexes.data))
+            .and_then(|access_list| access_list.as_ref())
+            .map(|items| {
+                items
+                    .iter()
+                    .map(|item| {
+                        (
+                            parse_u256(&item.address),
+                            item.storage_keys.iter().map(|k| parse_u256(k)).collect(),
+                        )
+                    })
+                    .collect()
+            });
+
+        let tx_type = match (&self.max_fee_per_gas, &self.gas_price) {
+            (Some(max_fee_per_gas), _) => {
+                let max_fee_per_gas = parse_u256(max_fee_per_gas);
+                let max_priority_fee_per_gas =
+                    parse_u256(self.max_priority_fee_per_gas.as_deref().unwrap_or("0x00"));
+                let access_list = access_list.unwrap_or_default();
+                match &self.blob_versioned_hashes {
+                    Some(blob_hashes) => TransactionType::Blob {
+                        max_fee_per_gas,
+                        max_priority_fee_per_gas,
+                        access_list,
+                        max_fee_per_blob_gas: parse_u256(
+                            self.max_fee_per_blob_gas.as_deref().unwrap_or("0x00"),
+                        ),
+                        blob_hashes: blob_hashes.iter().map(|h| parse_u256(h)).collect(),
+                    },
+                    None => TransactionType::DynamicFee {
+                        max_fee_per_gas,
+                        max_priority_fee_per_gas,
+                        access_list,
+                    },
+                }
+            }
+            (None, gas_price) => {
+                let gas_price = parse_u256(gas_price.as_deref().unwrap_or("0x00"));
+                match access_list {
+                    Some(access_list) => TransactionType::AccessList {
+                        gas_price,
+                        access_list,
+                    },
+                    None => TransactionType::Legacy { gas_price },
+                }
+            }
+        };
+
+        Transaction {
+            sender: match &self.sender {
+                Some(sender) => parse_u256(sender),
+                None => address_from_secret_key(&parse_bytes(&self.secret_key)),
+            },
+            to: if self.to.is_empty() {
+                None
+            } else {
+                Some(parse_u256(&self.to))
+            },
+            nonce: parse_usize(&self.nonce),
+            gas_limit: parse_u256(&self.gas_limit[indexes.gas]),
+            value: parse_u256(&self.value[indexes.value]),
+            data: parse_bytes(&self.data[indexes.data]),
+            tx_type,
+        }
+    }
+}
+
+/// The expected outcome of one transaction of the matrix
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostState {
+    /// the root of the state after the transaction
+    pub hash: String,
+
+    /// the hash of the RLP encoding of the logs of the transaction
+    pub logs: String,
+
+    pub indexes: PostStateIndexes,
+
+    /// the reason why the transaction is invalid, if it is
+    pub expect_exception: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PostStateIndexes {
+    pub data: usize,
+    pub gas: usize,
+    pub value: usize,
+}
+
+/// Parses a hexadecimal scalar, which may be written as `0x:bigint 0x...`
+/// when it does not fit in 64 bits
+pub fn parse_u256(str: &str) -> U256 {
+    let str = str.trim_start_matches("0x:bigint ");
+    let digits = str.strip_prefix("0x").unwrap_or(str);
+
+    if digits.is_empty() {
+        U256::zero()
+    } else {
+        U256::from_str_radix(digits, 16).unwrap()
+    }
+}
+
+fn parse_usize(str: &str) -> usize {
+    parse_u256(str).try_into().unwrap_or(usize::MAX)
+}
+
+fn parse_bytes(str: &str) -> Vec<u8> {
+    bytes_from_hex_str(str, false).unwrap()
+}
+
+/// Returns the address of the account controlled by `secret_key`, that is the
+/// rightmost 160 bits of the Keccak-256 hash of its uncompressed public key
+fn address_from_secret_key(secret_key: &[u8]) -> U256 {
+    let signing_key = SigningKey::from_slice(secret_key).unwrap();
+    let public_key = signing_key.verifying_key().to_encoded_point(false);
+
+    // the first byte of an uncompressed point is its tag
+    modulo_address_size(&keccak256(&public_key.as_bytes()[1..]))
+}
@@ -6,7 +6,7 @@ use serde::Deserialize;
 use super::{
     constants::KECCAK_EMPTY,
     helpers::bytes_from_hex_str,
-    types::{AccountState, BlockHeader, Input},
+    types::{AccountState, BlockHashes, BlockHeader, Input},
 };
 
 pub type Address = String;
@@ -19,6 +19,15 @@ pub struct TestAccountState {
     pub code: Option<StateCode>,
 }
 
+impl TestAccountState {
+    pub fn code(&self) -> Vec<u8> {
+        match &self.code {
+            Some(c) => bytes_from_hex_str(&c.bin, false).unwrap_or(Vec::new()),
+            None => Vec::new(),
+        }
+    }
+}
+
 impl From<&TestAccountState> for AccountState {
     fn from(account_state: &TestAccountState) -> Self {
         AccountState {
@@ -30,13 +39,8 @@ impl From<&TestAccountState> for AccountState {
                 Some(b) => U256::from_str_radix(&b, 16).unwrap_or(U256::zero()),
                 None => U256::zero(),
             },
-            code: match &account_state.code {
-                Some(c) => bytes_from_hex_str(&c.bin, false).unwrap_or(Vec::new()),
-                None => Vec::new(),
-            },
             code_hash: KECCAK_EMPTY,
             storage: HashMap::new(),
-            storage_root: KECCAK_EMPTY,
         }
     }
 }
@@ -45,6 +49,9 @@ impl From<&TestAccountState> for AccountState {
 pub struct EvmTest {
     pub name: String,
     pub hint: String,
+
+    /// the hard fork to run the test in, which is Cancun if it is missing
+    pub fork: Option<String>,
     pub code: Code,
     pub tx: Option<TxData>,
     pub block: Option<BlockData>,
@@ -78,6 +85,9 @@ pub struct TxData {
     pub gasprice: Option<String>,
     pub value: Option<String>,
     pub data: Option<String>,
+
+    /// the gas available to the execution, which is unlimited if it is missing
+    pub gas: Option<String>,
 }
 
 impl From<&TxData> for Input {
@@ -115,7 +125,28 @@ pub struct BlockData {
     pub number: Option<String>,
     pub gaslimit: Option<String>,
     pub difficulty: Option<String>,
+    pub prevrandao: Option<String>,
     pub chainid: Option<String>,
+
+    /// the hashes of the most recent blocks, indexed by block number
+    pub blockhashes: Option<HashMap<String, String>>,
+}
+
+impl BlockData {
+    pub fn block_hashes(&self) -> BlockHashes {
+        match &self.blockhashes {
+            Some(hashes) => hashes
+                .iter()
+                .map(|(number, hash)| {
+                    (
+                        U256::from_str_radix(number, 16).unwrap(),
+                        U256::from_str_radix(hash, 16).unwrap(),
+                    )
+                })
+                .collect(),
+            None => BlockHashes::new(),
+        }
+    }
 }
 
 impl From<&BlockData> for BlockHeader {
@@ -126,6 +157,11 @@ impl From<&BlockData> for BlockHeader {
         }
         if let Some(d) = &value.difficulty {
             block_header.difficulty = U256::from_str_radix(d, 16).unwrap();
+            // the tests written before the Merge use the difficulty as PREVRANDAO
+            block_header.prevrandao = block_header.difficulty;
+        }
+        if let Some(r) = &value.prevrandao {
+            block_header.prevrandao = U256::from_str_radix(r, 16).unwrap();
         }
         if let Some(n) = &value.number {
             block_header.number = U256::from_str_radix(n, 16).unwrap();
@@ -139,6 +175,9 @@ impl From<&BlockData> for BlockHeader {
         if let Some(f) = &value.basefee {
             block_header.base_fee = U256::from_str_radix(f, 16).unwrap();
         }
+        if let Some(c) = &value.chainid {
+            block_header.chain_id = U256::from_str_radix(c, 16).unwrap();
+        }
         block_header
     }
 }
@@ -0,0 +1,271 @@
+use primitive_types::U256;
+
+use super::{
+    constants::{MAX_BLOBS_PER_BLOCK, MAX_INITCODE_SIZE, VERSIONED_HASH_VERSION_KZG},
+    errors::InvalidTransaction,
+    gas::{intrinsic_gas, GAS_PER_BLOB},
+    spec::SpecId,
+    types::{AccessList, BlockHeader, GlobalState, Logs},
+};
+
+/// The fields of a transaction which depend on its type (EIP-2718)
+#[derive(Debug, Clone)]
+pub enum TransactionType {
+    /// a transaction with a fixed price of gas
+    Legacy { gas_price: U256 },
+
+    /// a transaction with a fixed price of gas and a list of accounts
+    /// and storage keys which are warm from its beginning (EIP-2930)
+    AccessList {
+        gas_price: U256,
+        access_list: AccessList,
+    },
+
+    /// a transaction which pays the base fee of the block, which is burnt,
+    /// and a priority fee to the beneficiary (EIP-1559)
+    DynamicFee {
+        max_fee_per_gas: U256,
+        max_priority_fee_per_gas: U256,
+        access_list: AccessList,
+    },
+
+    /// a dynamic fee transaction which carries blobs, whose gas
+    /// is paid at its own price and burnt (EIP-4844)
+    Blob {
+        max_fee_per_gas: U256,
+        max_priority_fee_per_gas: U256,
+        access_list: AccessList,
+        max_fee_per_blob_gas: U256,
+        blob_hashes: Vec<U256>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Transaction {
+    /// the address of the account which signed the transaction
+    pub sender: U256,
+
+    /// the recipient of the message call, or `None` for a contract creation
+    pub to: Option<U256>,
+
+    pub nonce: usize,
+    pub gas_limit: U256,
+    pub value: U256,
+
+    /// the input data of the message call,
+    /// or the initialisation code of the contract creation
+    pub data: Vec<u8>,
+
+    pub tx_type: TransactionType,
+}
+
+impl Transaction {
+    /// Returns the accounts and storage keys which are warm from
+    /// the beginning of the transaction, besides the default ones
+    pub fn access_list(&self) -> AccessList {
+        match &self.tx_type {
+            TransactionType::Legacy { .. } => Vec::new(),
+            TransactionType::AccessList { access_list, .. }
+            | TransactionType::DynamicFee { access_list, .. }
+            | TransactionType::Blob { access_list, .. } => access_list.clone(),
+        }
+    }
+
+    pub fn blob_hashes(&self) -> Vec<U256> {
+        match &self.tx_type {
+            TransactionType::Blob { blob_hashes, .. } => blob_hashes.clone(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The amount of blob gas used by the blobs of the transaction
+    pub fn blob_gas(&self) -> U256 {
+        U256::from(GAS_PER_BLOB) * self.blob_hashes().len()
+    }
+
+    /// The highest price the sender may pay for a unit of gas
+    pub fn max_fee_per_gas(&self) -> U256 {
+        match &self.tx_type {
+            TransactionType::Legacy { gas_price }
+            | TransactionType::AccessList { gas_price, .. } => *gas_price,
+            TransactionType::DynamicFee {
+                max_fee_per_gas, ..
+            }
+            | TransactionType::Blob {
+                max_fee_per_gas, ..
+            } => *max_fee_per_gas,
+        }
+    }
+
+    /// The price the sender actually pays for a unit of gas, which for
+    /// dynamic fee transactions is the base fee plus at most the priority fee
+    pub fn effective_gas_price(&self, base_fee: U256) -> U256 {
+        match &self.tx_type {
+            TransactionType::DynamicFee {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+                ..
+            }
+            | TransactionType::Blob {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+                ..
+            } => (*max_fee_per_gas).min(base_fee.saturating_add(*max_priority_fee_per_gas)),
+            _ => self.max_fee_per_gas(),
+        }
+    }
+
+    /// The hard fork which introduced the type of the transaction
+    pub fn introduced_in(&self) -> SpecId {
+        match &self.tx_type {
+            TransactionType::Legacy { .. } => SpecId::Frontier,
+            TransactionType::AccessList { .. } => SpecId::Berlin,
+            TransactionType::DynamicFee { .. } => SpecId::London,
+            TransactionType::Blob { .. } => SpecId::Cancun,
+        }
+    }
+
+    /// Returns the gas paid by the transaction before any of its code is executed
+    pub fn intrinsic_gas(&self, spec: SpecId) -> U256 {
+        intrinsic_gas(&self.data, self.to.is_none(), &self.access_list(), spec)
+    }
+
+    /// Checks that the transaction can be included in the block
+    /// with header `block_header`, on top of `state`
+    pub fn validate(
+        &self,
+        state: &GlobalState,
+        block_header: &BlockHeader,
+        spec: SpecId,
+    ) -> Result<(), InvalidTransaction> {
+        if !spec.is_enabled(self.introduced_in()) {
+            return Err(InvalidTransaction::TypeNotSupported);
+        }
+
+        let intrinsic_gas = self.intrinsic_gas(spec);
+        if self.gas_limit < intrinsic_gas {
+            return Err(InvalidTransaction::IntrinsicGasTooLow(
+                intrinsic_gas,
+                self.gas_limit,
+            ));
+        }
+        if self.gas_limit > block_header.gas_limit {
+            return Err(InvalidTransaction::GasLimitAboveBlock(
+                self.gas_limit,
+                block_header.gas_limit,
+            ));
+        }
+
+        // the initialisation code is limited in size from Shanghai (EIP-3860)
+        if self.to.is_none()
+            && spec.is_enabled(SpecId::Shanghai)
+            && self.data.len() > MAX_INITCODE_SIZE
+        {
+            return Err(InvalidTransaction::InitcodeSize(self.data.len()));
+        }
+
+        // the fees must cover the base fee from London (EIP-1559)
+        if spec.is_enabled(SpecId::London) {
+            if self.max_fee_per_gas() < block_header.base_fee {
+                return Err(InvalidTransaction::FeeBelowBaseFee(
+                    self.max_fee_per_gas(),
+                    block_header.base_fee,
+                ));
+            }
+            if let TransactionType::DynamicFee {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+                ..
+            }
+            | TransactionType::Blob {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+                ..
+            } = &self.tx_type
+            {
+                if max_priority_fee_per_gas > max_fee_per_gas {
+                    return Err(InvalidTransaction::PriorityFeeAboveMaxFee);
+                }
+            }
+        }
+
+        let mut max_blob_fee = U256::zero();
+        if let TransactionType::Blob {
+            max_fee_per_blob_gas,
+            blob_hashes,
+            ..
+        } = &self.tx_type
+        {
+            if self.to.is_none() {
+                return Err(InvalidTransaction::BlobCreation);
+            }
+            if blob_hashes.is_empty() || blob_hashes.len() > MAX_BLOBS_PER_BLOCK {
+                return Err(InvalidTransaction::BlobCount(blob_hashes.len()));
+            }
+            if let Some(blob_hash) = blob_hashes
+                .iter()
+                .find(|blob_hash| blob_hash.byte(31) != VERSIONED_HASH_VERSION_KZG)
+            {
+                return Err(InvalidTransaction::BlobHashVersion(*blob_hash));
+            }
+            if *max_fee_per_blob_gas < block_header.blob_base_fee {
+                return Err(InvalidTransaction::BlobFeeBelowBlobBaseFee(
+                    *max_fee_per_blob_gas,
+                    block_header.blob_base_fee,
+                ));
+            }
+            max_blob_fee = self.blob_gas().saturating_mul(*max_fee_per_blob_gas);
+        }
+
+        let (nonce, balance, has_code) = state
+            .get(&self.sender)
+            .map(|account_state| {
+                (
+                    account_state.nonce,
+                    account_state.balance,
+                    account_state.has_code(),
+                )
+            })
+            .unwrap_or((0, U256::zero(), false));
+
+        if nonce as u64 == u64::MAX {
+            return Err(InvalidTransaction::NonceOverflow);
+        }
+        if nonce != self.nonce {
+            return Err(InvalidTransaction::NonceMismatch(nonce, self.nonce));
+        }
+
+        // only externally owned accounts can send transactions (EIP-3607)
+        if has_code {
+            return Err(InvalidTransaction::SenderNotEOA);
+        }
+
+        let max_cost = self
+            .gas_limit
+            .saturating_mul(self.max_fee_per_gas())
+            .saturating_add(max_blob_fee)
+            .saturating_add(self.value);
+        if balance < max_cost {
+            return Err(InvalidTransaction::InsufficientFunds(balance, max_cost));
+        }
+
+        Ok(())
+    }
+}
+
+/// The outcome of a transaction which was executed
+#[derive(Debug)]
+pub struct TransactionResult {
+    pub success: bool,
+
+    /// the return data of the message call, or the revert data
+    pub output: Vec<u8>,
+
+    /// the amount of gas paid by the sender, after the refund
+    pub gas_used: U256,
+
+    pub logs: Logs,
+
+    /// the address of the contract deployed by a contract creation
+    pub contract_address: Option<U256>,
+}
@@ -0,0 +1,175 @@
+// The modified Merkle Patricia trie of Appendix D of the yellow paper, which
+// commits to the world state and to the storage of each account. Only the
+// root hash is computed, from the whole set of items
+
+use primitive_types::U256;
+
+use super::{
+    constants::EMPTY_TRIE_ROOT,
+    helpers::keccak256,
+    rlp,
+    types::{AccountState, GlobalState, Storage},
+};
+
+/// Returns the root hash of the trie which maps each key to its value
+pub fn trie_root(items: &[(Vec<u8>, Vec<u8>)]) -> U256 {
+    if items.is_empty() {
+        return EMPTY_TRIE_ROOT;
+    }
+
+    let mut items: Vec<(Vec<u8>, &[u8])> = items
+        .iter()
+        .map(|(key, value)| (nibbles(key), value.as_slice()))
+        .collect();
+    items.sort();
+
+    keccak256(&encode_node(&items, 0))
+}
+
+/// Returns the root of the storage trie of an account, which maps the hash of
+/// each key to the RLP encoding of its value. Slots set to zero are left out
+pub fn storage_root(storage: &Storage) -> U256 {
+    let items: Vec<(Vec<u8>, Vec<u8>)> = storage
+        .iter()
+        .filter(|(_, value)| !value.is_zero())
+        .map(|(key, value)| {
+            (
+                u256_to_bytes(keccak256(&u256_to_bytes(*key))),
+                rlp::encode_u256(*value),
+            )
+        })
+        .collect();
+
+    trie_root(&items)
+}
+
+/// Returns the root of the world state trie, which maps the hash of each
+/// address to the RLP encoding of its account
+pub fn state_root(state: &GlobalState) -> U256 {
+    let items: Vec<(Vec<u8>, Vec<u8>)> = state
+        .iter()
+        .map(|(address, account_state)| {
+            (
+                u256_to_bytes(keccak256(&u256_to_bytes(*address)[12..])),
+                encode_account(account_state),
+            )
+        })
+        .collect();
+
+    trie_root(&items)
+}
+
+/// Encodes an account as `[nonce, balance, storage_root, code_hash]`
+fn encode_account(account_state: &AccountState) -> Vec<u8> {
+    rlp::encode_list(&[
+        rlp::encode_u256(U256::from(account_state.nonce)),
+        rlp::encode_u256(account_state.balance),
+        rlp::encode_hash(storage_root(&account_state.storage)),
+        rlp::encode_hash(account_state.code_hash),
+    ])
+}
+
+/// Models the function c in the yellow paper: returns the RLP encoding of
+/// the node whose descendants are `items`, sorted by key, whose keys share
+/// their first `level` nibbles
+fn encode_node(items: &[(Vec<u8>, &[u8])], level: usize) -> Vec<u8> {
+    if items.is_empty() {
+        return rlp::encode_bytes(&[]);
+    }
+
+    // leaf
+    if items.len() == 1 {
+        let (key, value) = &items[0];
+        return rlp::encode_list(&[
+            rlp::encode_bytes(&hex_prefix(&key[level..], true)),
+            rlp::encode_bytes(value),
+        ]);
+    }
+
+    // extension, if all the keys share more nibbles
+    let first_key = &items[0].0[level..];
+    let shared_nibbles = items
+        .iter()
+        .map(|(key, _)| common_prefix_length(first_key, &key[level..]))
+        .min()
+        .unwrap_or(0);
+    if shared_nibbles > 0 {
+        return rlp::encode_list(&[
+            rlp::encode_bytes(&hex_prefix(&first_key[..shared_nibbles], false)),
+            encode_child(items, level + shared_nibbles),
+        ]);
+    }
+
+    // branch. As the keys are sorted, the one which ends here comes first
+    // and the ones sharing the next nibble are contiguous
+    let mut rest = items;
+    let mut value: &[u8] = &[];
+    if rest[0].0.len() == level {
+        value = rest[0].1;
+        rest = &rest[1..];
+    }
+
+    let mut branch = Vec::with_capacity(17);
+    for nibble in 0..16 {
+        let count = rest
+            .iter()
+            .take_while(|(key, _)| key[level] == nibble)
+            .count();
+        branch.push(encode_child(&rest[..count], level + 1));
+        rest = &rest[count..];
+    }
+    branch.push(rlp::encode_bytes(value));
+
+    rlp::encode_list(&branch)
+}
+
+/// Models the function n in the yellow paper: a child node is embedded
+/// in its parent if its encoding is shorter than 32 bytes,
+/// otherwise it is referenced by its hash
+fn encode_child(items: &[(Vec<u8>, &[u8])], level: usize) -> Vec<u8> {
+    let encoded = encode_node(items, level);
+
+    if encoded.len() < 32 {
+        encoded
+    } else {
+        rlp::encode_hash(keccak256(&encoded))
+    }
+}
+
+/// Models the hex-prefix encoding HP in the yellow paper, which packs
+/// `nibbles` into bytes together with whether they belong to a leaf
+fn hex_prefix(nibbles: &[u8], leaf: bool) -> Vec<u8> {
+    let flag = if leaf { 2 } else { 0 };
+
+    let mut bytes = Vec::with_capacity(nibbles.len() / 2 + 1);
+    let rest = if nibbles.len() % 2 == 1 {
+        bytes.push(((flag + 1) << 4) | nibbles[0]);
+        &nibbles[1..]
+    } else {
+        bytes.push(flag << 4);
+        nibbles
+    };
+    for pair in rest.chunks(2) {
+        bytes.push((pair[0] << 4) | pair[1]);
+    }
+
+    bytes
+}
+
+fn nibbles(bytes: &[u8]) -> Vec<u8> {
+    bytes
+        .iter()
+        .flat_map(|byte| [byte >> 4, byte & 0x0f])
+        .collect()
+}
+
+fn common_prefix_length(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b).take_while(|(x, y)| x == y).count()
+}
+
+fn u256_to_bytes(value: U256) -> Vec<u8> {
+    let mut bytes = [0_u8; 32];
+    value.to_big_endian(&mut bytes);
+
+    bytes.to_vec()
+}
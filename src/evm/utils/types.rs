@@ -1,18 +1,54 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
+    sync::Arc,
+};
 
 use primitive_types::U256;
 
+use crate::evm::precompiles::get_precompiles;
+
 use super::{
-    constants::{KECCAK_EMPTY, TEST_CONTRACT_ADDRESS, TEST_EOA_ADDRESS},
+    constants::{
+        BLOCK_HASH_HISTORY, CHAIN_ID, KECCAK_EMPTY, TEST_CONTRACT_ADDRESS, TEST_EOA_ADDRESS,
+    },
+    database::{Database, InMemoryDatabase},
     errors::EVMError,
+    helpers::{is_account_empty, keccak256},
+    journal::JournalEntry,
+    spec::SpecId,
+    trie::state_root,
 };
 
 #[derive(Debug, Clone)]
 pub struct ExecutionContext {
+    /// the accounts read from `db`, with the changes made to them,
+    /// together with the accounts created by the execution
     pub global_state: GlobalState,
     pub machine_state: MachineState,
     pub accrued_substate: AccruedSubstate,
     pub input: Input,
+
+    /// the value that each written storage slot, identified by the
+    /// account address and the key, had at the beginning of the transaction
+    pub original_storage: HashMap<(U256, U256), U256>,
+
+    /// the rules of the hard fork the execution follows
+    pub spec: SpecId,
+
+    /// the world state the execution starts from, which also
+    /// knows the hashes of the ancestors of the present block
+    pub db: Arc<dyn Database>,
+
+    /// which accounts and storage slots have been read from `db`
+    pub loaded: LoadedState,
+
+    /// the code of the accounts, indexed by its hash. Since it is only
+    /// ever added to, its changes are not recorded in the journal
+    pub code_store: CodeStore,
+
+    /// the changes made to the state during the transaction, from the
+    /// oldest to the newest, which are undone to revert to a checkpoint
+    pub journal: Vec<JournalEntry>,
 }
 
 impl ExecutionContext {
@@ -22,17 +58,243 @@ impl ExecutionContext {
             machine_state: MachineState::new(),
             accrued_substate: AccruedSubstate::new(),
             input: Input::new_demo(),
+            original_storage: HashMap::new(),
+            spec: SpecId::default(),
+            db: Arc::new(InMemoryDatabase::default()),
+            loaded: LoadedState::default(),
+            code_store: CodeStore::new(),
+            journal: Vec::new(),
+        }
+    }
+
+    /// Returns the code of the account at `address`, which is empty if the
+    /// account does not exist. It is shared with the code store, not copied
+    pub fn code(&self, address: U256) -> Arc<[u8]> {
+        self.global_state
+            .get(&address)
+            .and_then(|account_state| self.code_store.get(&account_state.code_hash))
+            .cloned()
+            .unwrap_or_else(|| Arc::from([]))
+    }
+
+    /// Returns the code whose Keccak-256 hash is `code_hash`, which is
+    /// read from the database if no account in the global state has it
+    pub fn code_by_hash(&self, code_hash: U256) -> Arc<[u8]> {
+        match self.code_store.get(&code_hash) {
+            Some(code) => code.clone(),
+            None => self.db.code_by_hash(code_hash),
+        }
+    }
+
+    /// Installs `code` as the code of the account at `address`,
+    /// which is created if it does not exist
+    pub fn set_code(&mut self, address: U256, code: Vec<u8>) {
+        let code_hash = keccak256(&code);
+        self.code_store.insert(code_hash, code.into());
+        self.account_mut(address).code_hash = code_hash;
+    }
+
+    /// Puts `account_state` at `address` in the global state, with `code` as
+    /// its code, outside of the journal: it is meant to set up the world state
+    /// before running a transaction. The code hash of `account_state` is ignored
+    pub fn insert_account(&mut self, address: U256, account_state: AccountState, code: Vec<u8>) {
+        let code_hash = keccak256(&code);
+        self.code_store.insert(code_hash, code.into());
+        self.global_state.insert(
+            address,
+            AccountState {
+                code_hash,
+                ..account_state
+            },
+        );
+    }
+
+    /// Models the function P in the yellow paper: returns the hash of the
+    /// block `number`, or zero if it is not one of the 256 most recent
+    /// ancestors of the present block or if its hash is unknown
+    pub fn block_hash(&self, number: U256) -> U256 {
+        let current = self.input.block_header.number;
+
+        if number >= current || current - number > U256::from(BLOCK_HASH_HISTORY) {
+            return U256::zero();
+        }
+
+        self.db.block_hash(number)
+    }
+
+    /// Reads the account at `address` from the database into the global
+    /// state, together with its code, the first time it is accessed. An
+    /// account which is already in the global state is not read, so that the
+    /// global state can be filled beforehand to override the database
+    pub fn load_account(&mut self, address: U256) {
+        if self.loaded.detached
+            || !self.loaded.accounts.insert(address)
+            || self.global_state.contains_key(&address)
+        {
+            return;
+        }
+
+        if let Some(account_state) = self.db.basic(address) {
+            if account_state.has_code() && !self.code_store.contains_key(&account_state.code_hash) {
+                let code = self.db.code_by_hash(account_state.code_hash);
+                self.code_store.insert(account_state.code_hash, code);
+            }
+            self.global_state.insert(address, account_state);
+        }
+    }
+
+    /// Like `load_account`, but also reads the storage slot `key` of the account
+    pub fn load_storage(&mut self, address: U256, key: U256) {
+        self.load_account(address);
+
+        // the storage of an account which was deleted or created
+        // again is no longer the one in the database
+        if self.loaded.detached
+            || self.loaded.cleared_storage.contains(&address)
+            || !self.loaded.storage.insert((address, key))
+        {
+            return;
+        }
+
+        if let Some(account_state) = self.global_state.get_mut(&address) {
+            if let Entry::Vacant(entry) = account_state.storage.entry(key) {
+                let value = self.db.storage(address, key);
+                if !value.is_zero() {
+                    entry.insert(value);
+                }
+            }
+        }
+    }
+
+    /// Whether the account at `address` has a storage slot which is not zero,
+    /// either among the slots in the global state or among those in the
+    /// database, which no longer count once its storage has been cleared
+    pub fn has_storage(&self, address: U256) -> bool {
+        let in_global_state = self
+            .global_state
+            .get(&address)
+            .is_some_and(|account_state| {
+                account_state.storage.values().any(|value| !value.is_zero())
+            });
+
+        let in_database = !self.loaded.detached
+            && !self.loaded.cleared_storage.contains(&address)
+            && self.db.has_storage(address);
+
+        in_global_state || in_database
+    }
+
+    /// Empties the world state, which from then on is made of the accounts
+    /// put in the global state only: accounts and storage are no longer
+    /// read from the database, which still gives the hashes of the blocks
+    pub fn clear_world_state(&mut self) {
+        self.global_state.clear();
+        self.loaded = LoadedState {
+            detached: true,
+            ..Default::default()
+        };
+        self.journal.clear();
+    }
+
+    /// Applies the changes which take place once a transaction is over:
+    /// the self-destructed accounts are deleted, together with the touched
+    /// accounts which are empty from Spurious Dragon (EIP-161),
+    /// and the transient storage is discarded.
+    /// The changes made so far can no longer be reverted
+    pub fn finalise(&mut self) {
+        let substate = &mut self.accrued_substate;
+
+        substate.self_destruct_set.drain().for_each(|address| {
+            self.global_state.remove(&address);
+            self.loaded.cleared_storage.insert(address);
+        });
+
+        if self.spec.is_enabled(SpecId::SpuriousDragon) {
+            substate.touched_accounts.drain().for_each(|address| {
+                if is_account_empty(&self.global_state, address) {
+                    self.global_state.remove(&address);
+                    self.loaded.cleared_storage.insert(address);
+                }
+            });
         }
+
+        substate.created_accounts.clear();
+        substate.transient_storage.clear();
+        self.journal.clear();
+    }
+
+    /// Returns the whole world state: the accounts of the database with the
+    /// changes in the global state applied to them, or `None` if the
+    /// database cannot list its accounts. Once the world state has been
+    /// replaced with `clear_world_state`, it is just the global state
+    pub fn world_state(&self) -> Option<GlobalState> {
+        let mut world_state = if self.loaded.detached {
+            GlobalState::new()
+        } else {
+            self.db.accounts()?
+        };
+
+        // an account which was read or whose storage was cleared, and which
+        // is no longer in the global state, has been deleted
+        self.loaded
+            .accounts
+            .iter()
+            .chain(&self.loaded.cleared_storage)
+            .filter(|address| !self.global_state.contains_key(address))
+            .for_each(|address| {
+                world_state.remove(address);
+            });
+
+        for (address, account_state) in &self.global_state {
+            let mut account_state = account_state.clone();
+
+            // the slots which were not read still hold the value in the
+            // database, unless the storage of the account was cleared
+            if let Some(db_account_state) = world_state.get(address) {
+                if !self.loaded.cleared_storage.contains(address) {
+                    for (key, value) in &db_account_state.storage {
+                        account_state.storage.entry(*key).or_insert(*value);
+                    }
+                }
+            }
+            world_state.insert(*address, account_state);
+        }
+
+        Some(world_state)
+    }
+
+    /// Returns the root of the world state trie,
+    /// or `None` if the world state is not known
+    pub fn state_root(&self) -> Option<U256> {
+        self.world_state()
+            .map(|world_state| state_root(&world_state))
     }
 }
 
+/// Keeps track of what has been read from the database, after which
+/// the global state is the only source of truth for it. Reverting a change
+/// never brings back what was read, which is still the one in the database
+#[derive(Debug, Clone, Default)]
+pub struct LoadedState {
+    pub accounts: HashSet<U256>,
+    pub storage: HashSet<(U256, U256)>,
+
+    /// the accounts whose storage in the database is out of date
+    /// since they were deleted or created again
+    pub cleared_storage: HashSet<U256>,
+
+    /// whether the world state was replaced, so that the global state
+    /// holds all of it and the database is not read anymore
+    pub detached: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct AccountState {
     pub nonce: usize,
     pub balance: U256,
+    /// the hash of the code of the account, which
+    /// is stored in `ExecutionContext::code_store`
     pub code_hash: U256,
-    pub code: Vec<u8>,
-    pub storage_root: U256,
     pub storage: Storage,
 }
 
@@ -42,11 +304,13 @@ impl AccountState {
             nonce: 0,
             balance: U256::zero(),
             code_hash: KECCAK_EMPTY,
-            code: Vec::new(),
-            storage_root: KECCAK_EMPTY,
             storage: HashMap::new(),
         }
     }
+
+    pub fn has_code(&self) -> bool {
+        self.code_hash != KECCAK_EMPTY
+    }
 }
 
 pub type GlobalState = HashMap<U256, AccountState>;
@@ -60,6 +324,13 @@ pub fn get_demo_global_state() -> GlobalState {
 
 pub type Storage = HashMap<U256, U256>;
 
+/// The code of the accounts, indexed by its Keccak-256 hash. The code is
+/// shared with the contexts running it and with the copies of the store
+pub type CodeStore = HashMap<U256, Arc<[u8]>>;
+
+/// The hashes of past blocks, indexed by block number
+pub type BlockHashes = HashMap<U256, U256>;
+
 #[derive(Debug, Clone)]
 pub struct MachineState {
     pub pc: usize,
@@ -115,6 +386,14 @@ pub struct AccruedSubstate {
     /// (more accurately, each element of it is a tuple of a
     /// 20-byte account address and a 32-byte storage slot)
     pub accessed_storage_keys: HashSet<(U256, U256)>,
+
+    /// the storage which is discarded at the end of the transaction,
+    /// indexed by account address and key (EIP-1153)
+    pub transient_storage: HashMap<(U256, U256), U256>,
+
+    /// the set of accounts created during the transaction, which
+    /// are the only ones SELFDESTRUCT can delete from Cancun (EIP-6780)
+    pub created_accounts: HashSet<U256>,
 }
 
 impl AccruedSubstate {
@@ -126,10 +405,50 @@ impl AccruedSubstate {
             refund_balance: U256::zero(),
             accessed_accounts: HashSet::new(),
             accessed_storage_keys: HashSet::new(),
+            transient_storage: HashMap::new(),
+            created_accounts: HashSet::new(),
         }
     }
+
+    /// Marks as accessed the accounts which are warm since the beginning
+    /// of a transaction: its sender, its recipient and the precompiled
+    /// contracts (EIP-2929), then the coinbase from Shanghai (EIP-3651)
+    pub fn warm_up(&mut self, input: &Input, spec: SpecId) {
+        self.accessed_accounts.insert(input.origin);
+        self.accessed_accounts.insert(input.address);
+        self.accessed_accounts.extend(get_precompiles(spec).keys());
+        if spec.is_enabled(SpecId::Shanghai) {
+            self.accessed_accounts
+                .insert(input.block_header.beneficiary);
+        }
+    }
+
+    /// Returns the accessed accounts and storage keys in the format
+    /// of an EIP-2930 access list, sorted by address and key
+    pub fn access_list(&self) -> AccessList {
+        let mut access_list: AccessList = self
+            .accessed_accounts
+            .iter()
+            .map(|address| {
+                let mut keys: Vec<U256> = self
+                    .accessed_storage_keys
+                    .iter()
+                    .filter(|(key_address, _)| key_address == address)
+                    .map(|(_, key)| *key)
+                    .collect();
+                keys.sort();
+                (*address, keys)
+            })
+            .collect();
+        access_list.sort_by_key(|(address, _)| *address);
+
+        access_list
+    }
 }
 
+/// A list of addresses, each one with a list of storage keys
+pub type AccessList = Vec<(U256, Vec<U256>)>;
+
 /// Input has defined in the yellow paper
 #[derive(Debug, Clone)]
 pub struct Input {
@@ -163,7 +482,7 @@ pub struct Input {
     pub block_header: BlockHeader,
 
     /// the byte array that is the machine code to be executed.
-    pub bytecode: Vec<u8>,
+    pub bytecode: Arc<[u8]>,
 
     /// the depth of the present message-call or
     /// contract-creation (i.e. the number of CALLs or
@@ -174,6 +493,10 @@ pub struct Input {
     /// This is also referred as static execution context,
     /// however `static` is a reserved keyword.
     pub write: bool,
+
+    /// the versioned hashes of the blobs carried by the
+    /// transaction (EIP-4844)
+    pub blob_hashes: Vec<U256>,
 }
 
 impl Input {
@@ -186,10 +509,11 @@ impl Input {
             data: Vec::new(),
             sender: TEST_EOA_ADDRESS,
             value: U256::zero(),
-            bytecode: Vec::new(),
+            bytecode: Arc::from([]),
             block_header: BlockHeader::new(),
             depth: 0,
             write: true,
+            blob_hashes: Vec::new(),
         }
     }
 }
@@ -205,6 +529,10 @@ pub struct BlockHeader {
     /// level and the timestamp
     pub difficulty: U256,
 
+    /// The RANDAO mix of the previous block, which replaces the difficulty
+    /// from Paris (EIP-4399)
+    pub prevrandao: U256,
+
     /// A scalar value equal to the number of ancestor blocks.
     /// The genesis block has a number of zero
     pub number: U256,
@@ -217,6 +545,12 @@ pub struct BlockHeader {
     pub gas_limit: U256,
 
     pub base_fee: U256,
+
+    /// The price of the gas used by blobs in this block (EIP-4844)
+    pub blob_base_fee: U256,
+
+    /// The identifier of the chain the block belongs to (EIP-155)
+    pub chain_id: U256,
 }
 
 impl BlockHeader {
@@ -224,10 +558,13 @@ impl BlockHeader {
         Self {
             beneficiary: U256::zero(),
             difficulty: U256::zero(),
+            prevrandao: U256::zero(),
             number: U256::zero(),
             timestamp: U256::zero(),
             gas_limit: U256::zero(),
             base_fee: U256::zero(),
+            blob_base_fee: U256::zero(),
+            chain_id: U256::from(CHAIN_ID),
         }
     }
 }
@@ -236,6 +573,15 @@ impl BlockHeader {
 pub struct EVMReturnData {
     pub output: Option<Vec<u8>>,
     pub success: bool,
+
+    /// the amount of gas consumed by this execution, which is all of it
+    /// in case of an exceptional halt
+    pub gas_used: U256,
+
+    /// the amount of gas given back from the refund balance. Refunds are
+    /// only paid at the end of the transaction, so this is zero unless
+    /// this is the outermost execution
+    pub gas_refunded: U256,
 }
 
 pub type OpcodeResult<'a> = Result<Option<Vec<u8>>, EVMError>;
@@ -251,3 +597,17 @@ pub type Logs = Vec<Log>;
 
 pub type Opcode = Box<dyn Fn(&mut ExecutionContext) -> OpcodeResult>;
 pub type Opcodes = HashMap<u8, Opcode>;
+
+/// The output of a precompiled contract which ran successfully
+#[derive(Debug)]
+pub struct PrecompileOutput {
+    pub output: Vec<u8>,
+    pub gas_used: U256,
+}
+
+/// A precompiled contract fails, consuming all the gas it is given,
+/// if it runs out of gas or if its input is invalid
+pub type PrecompileResult = Option<PrecompileOutput>;
+
+pub type Precompile = Box<dyn Fn(&[u8], U256) -> PrecompileResult>;
+pub type Precompiles = HashMap<U256, Precompile>;
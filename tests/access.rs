@@ -0,0 +1,54 @@
+mod common;
+
+use common::{context, execute, RECIPIENT};
+use evm_from_scratch_new::evm::utils::spec::SpecId;
+use primitive_types::U256;
+
+const COLD: U256 = U256([0xdead, 0, 0, 0]);
+
+#[test]
+fn second_access_to_an_account_is_warm() {
+    // BALANCE of 0xdead, twice
+    let code = "61dead3161dead31";
+
+    let mut ctx = context(SpecId::Berlin);
+    let result = execute(&mut ctx, code, 100_000);
+    assert!(result.success);
+    assert_eq!(result.gas_used, U256::from(3 + 2600 + 3 + 100));
+    assert!(ctx.accrued_substate.accessed_accounts.contains(&COLD));
+
+    // before Berlin (EIP-2929) every access costs the same
+    let mut ctx = context(SpecId::Istanbul);
+    let result = execute(&mut ctx, code, 100_000);
+    assert_eq!(result.gas_used, U256::from(3 + 700 + 3 + 700));
+}
+
+#[test]
+fn second_access_to_a_storage_slot_is_warm() {
+    // SLOAD of the slot 1, twice
+    let code = "600154600154";
+
+    let mut ctx = context(SpecId::Berlin);
+    let result = execute(&mut ctx, code, 100_000);
+    assert_eq!(result.gas_used, U256::from(3 + 2100 + 3 + 100));
+    assert!(ctx
+        .accrued_substate
+        .accessed_storage_keys
+        .contains(&(RECIPIENT, U256::one())));
+}
+
+#[test]
+fn executing_account_and_precompiles_are_warm_from_the_start() {
+    // BALANCE of itself and of the precompiled contract 0x01
+    let mut ctx = context(SpecId::Berlin);
+    let result = execute(&mut ctx, "3031600131", 100_000);
+    assert_eq!(result.gas_used, U256::from(2 + 100 + 3 + 100));
+}
+
+#[test]
+fn cold_access_without_enough_gas_is_an_exceptional_halt() {
+    let mut ctx = context(SpecId::Berlin);
+    let result = execute(&mut ctx, "61dead31", 2_000);
+    assert!(!result.success);
+    assert_eq!(result.gas_used, U256::from(2_000));
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use std::{collections::HashMap, sync::Arc};
+
+use common::{context, execute};
+use evm_from_scratch_new::evm::utils::{database::InMemoryDatabase, spec::SpecId};
+use primitive_types::U256;
+
+#[test]
+fn blockhash_reads_the_last_256_blocks_from_the_database() {
+    let mut ctx = context(SpecId::Cancun);
+    ctx.input.block_header.number = U256::from(300);
+    let block_hashes = HashMap::from([
+        (U256::from(299), U256::from(0x0299)),
+        (U256::from(44), U256::from(0x0044)),
+        (U256::from(43), U256::from(0x0043)),
+    ]);
+    ctx.db = Arc::new(InMemoryDatabase {
+        block_hashes,
+        ..Default::default()
+    });
+
+    // BLOCKHASH of the blocks 299, 44, 43 and 300
+    let result = execute(&mut ctx, "61012b40602c40602b4061012c40", 100_000);
+    assert!(result.success);
+    assert_eq!(result.gas_used, U256::from(4 * (3 + 20)));
+
+    // the block 43 is too old, and the block 300 is the present one
+    assert_eq!(
+        ctx.machine_state.stack,
+        [
+            U256::from(0x0299),
+            U256::from(0x0044),
+            U256::zero(),
+            U256::zero()
+        ]
+    );
+}
+
+#[test]
+fn chainid_and_prevrandao_read_the_block_header() {
+    let run = |spec| {
+        let mut ctx = context(spec);
+        ctx.input.block_header.chain_id = U256::from(11155111);
+        ctx.input.block_header.difficulty = U256::from(0xd1ff);
+        ctx.input.block_header.prevrandao = U256::from(0x7a4d);
+
+        // CHAINID and PREVRANDAO
+        assert!(execute(&mut ctx, "4644", 100_000).success);
+        ctx.machine_state.stack
+    };
+
+    assert_eq!(
+        run(SpecId::Cancun),
+        [U256::from(11155111), U256::from(0x7a4d)]
+    );
+
+    // PREVRANDAO is DIFFICULTY before Paris (EIP-4399)
+    assert_eq!(
+        run(SpecId::London),
+        [U256::from(11155111), U256::from(0xd1ff)]
+    );
+}
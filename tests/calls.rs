@@ -0,0 +1,114 @@
+mod common;
+
+use common::{balance, context, execute, install, RECIPIENT};
+use evm_from_scratch_new::evm::{
+    opcodes::system::call,
+    utils::{errors::EVMError, spec::SpecId},
+};
+use primitive_types::U256;
+
+const CALLEE: U256 = U256([0xbeef, 0, 0, 0]);
+
+#[test]
+fn callcode_runs_the_code_of_the_callee_on_the_caller() {
+    let mut ctx = context(SpecId::Cancun);
+    install(&mut ctx, RECIPIENT, 10, "");
+
+    // stores 1 in the slot 0
+    install(&mut ctx, CALLEE, 0, "6001600055");
+
+    // CALLCODE 0xbeef with 3 wei
+    let result = execute(&mut ctx, "6000600060006000600361beef5af2", 100_000);
+    assert!(result.success);
+    assert_eq!(ctx.machine_state.stack, [U256::one()]);
+
+    assert_eq!(
+        ctx.global_state[&RECIPIENT].storage[&U256::zero()],
+        U256::one()
+    );
+    assert!(ctx.global_state[&CALLEE].storage.is_empty());
+
+    // the value is sent by the caller to itself
+    assert_eq!(balance(&ctx, RECIPIENT), U256::from(10));
+    assert_eq!(balance(&ctx, CALLEE), U256::zero());
+}
+
+#[test]
+fn call_forwards_all_but_one_64th_of_the_gas_left() {
+    let mut ctx = context(SpecId::Cancun);
+
+    // returns the gas left after GAS
+    install(&mut ctx, CALLEE, 0, "5a60005260206000f3");
+
+    // CALL 0xbeef asking for more gas than there is, copying
+    // the 32 bytes it returns to the memory, and MLOAD them
+    let code = "6020600060006000600061beef63fffffffff150600051";
+    let result = execute(&mut ctx, code, 100_000);
+    assert!(result.success);
+
+    // seven pushes, the cold access to 0xbeef and the expansion of the memory
+    let gas_left = 100_000 - 7 * 3 - 2600 - 3;
+    let callee_gas = gas_left - gas_left / 64;
+    assert_eq!(ctx.machine_state.stack, [U256::from(callee_gas - 2)]);
+}
+
+#[test]
+fn call_and_delegatecall_pass_the_calldata() {
+    // returns its calldata
+    let echo = "366000600037366000f3";
+
+    // stores 0x2a in the memory, sends it to 0xbeef, copying what
+    // it returns after it, and MLOAD what was returned
+    for code in [
+        "602a6000526020602060206000600061beef5af150602051",
+        "602a600052602060206020600061beef5af450602051",
+    ] {
+        let mut ctx = context(SpecId::Cancun);
+        install(&mut ctx, CALLEE, 0, echo);
+
+        let result = execute(&mut ctx, code, 100_000);
+        assert!(result.success);
+        assert_eq!(ctx.machine_state.stack, [U256::from(0x2a)], "{}", code);
+    }
+}
+
+#[test]
+fn static_call_with_value_is_a_write_protection_error() {
+    let mut ctx = context(SpecId::Cancun);
+    install(&mut ctx, RECIPIENT, 10, "");
+    ctx.input.address = RECIPIENT;
+    ctx.input.write = false;
+    ctx.machine_state.gas = U256::from(100_000);
+
+    // the arguments of CALL, from the last one: 1 wei to 0xbeef with 1000 gas
+    let arguments = [0, 0, 0, 0, 1, 0xbeef, 1000];
+    ctx.machine_state.stack = arguments.into_iter().map(U256::from).collect();
+
+    assert!(matches!(
+        call(&mut ctx),
+        Err(EVMError::WriteProtectionError(_))
+    ));
+}
+
+#[test]
+fn static_mode_ends_when_staticcall_returns() {
+    let mut ctx = context(SpecId::Cancun);
+    install(&mut ctx, RECIPIENT, 10, "");
+
+    // CALL 0xdead with 1 wei, which halts under STATICCALL
+    install(&mut ctx, CALLEE, 10, "6000600060006000600161dead5af1");
+
+    // STATICCALL 0xbeef with 10000 gas, and store 1 in the slot 0
+    let result = execute(
+        &mut ctx,
+        "600060006000600061beef612710fa6001600055",
+        100_000,
+    );
+    assert!(result.success);
+    assert_eq!(ctx.machine_state.stack, [U256::zero()]);
+    assert_eq!(
+        ctx.global_state[&RECIPIENT].storage[&U256::zero()],
+        U256::one()
+    );
+    assert_eq!(balance(&ctx, CALLEE), U256::from(10));
+}
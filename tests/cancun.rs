@@ -0,0 +1,56 @@
+mod common;
+
+use common::{context, execute, install, transaction, RECIPIENT};
+use evm_from_scratch_new::evm::{evm::EVM, utils::spec::SpecId};
+use primitive_types::U256;
+
+#[test]
+fn tload_reads_what_tstore_wrote() {
+    // TSTORE 0x2a in the slot 0 and TLOAD it
+    let mut ctx = context(SpecId::Cancun);
+    let result = execute(&mut ctx, "602a60005d60005c", 100_000);
+    assert!(result.success);
+    assert_eq!(ctx.machine_state.stack, [U256::from(0x2a)]);
+    assert_eq!(result.gas_used, U256::from(3 + 3 + 100 + 3 + 100));
+}
+
+#[test]
+fn transient_storage_is_discarded_after_the_transaction() {
+    let mut ctx = context(SpecId::Cancun);
+    install(&mut ctx, RECIPIENT, 0, "602a60005d");
+
+    let tx = transaction(Some(RECIPIENT), 100_000, 0, Vec::new());
+    assert!(EVM::transact(&mut ctx, &tx).unwrap().success);
+    assert!(ctx.accrued_substate.transient_storage.is_empty());
+    assert!(ctx.global_state[&RECIPIENT].storage.is_empty());
+}
+
+#[test]
+fn mcopy_copies_memory_even_when_the_ranges_overlap() {
+    // MSTORE 0x2a at 0, MCOPY the word to 32 and MLOAD it
+    let mut ctx = context(SpecId::Cancun);
+    assert!(execute(&mut ctx, "602a6000526020600060205e602051", 100_000).success);
+    assert_eq!(ctx.machine_state.stack, [U256::from(0x2a)]);
+
+    // MSTORE 0x0102 at 0, MCOPY its first 31 bytes one byte
+    // further, which moves 0x01 to the last byte, and MLOAD 0
+    let mut ctx = context(SpecId::Cancun);
+    assert!(execute(&mut ctx, "610102600052601f600060015e600051", 100_000).success);
+    assert_eq!(ctx.machine_state.stack, [U256::one()]);
+}
+
+#[test]
+fn blobhash_and_blobbasefee_read_the_transaction_and_the_block() {
+    let mut ctx = context(SpecId::Cancun);
+    let blob_hashes = vec![U256::from(0x0100), U256::from(0x0101)];
+    ctx.input.blob_hashes = blob_hashes.clone();
+    ctx.input.block_header.blob_base_fee = U256::from(7);
+
+    // BLOBHASH of the indexes 1 and 2, and BLOBBASEFEE
+    let result = execute(&mut ctx, "6001496002494a", 100_000);
+    assert!(result.success);
+    assert_eq!(
+        ctx.machine_state.stack,
+        [blob_hashes[1], U256::zero(), U256::from(7)]
+    );
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::{context, install, transaction, RECIPIENT, SENDER};
+use evm_from_scratch_new::evm::{
+    evm::EVM,
+    utils::{
+        constants::KECCAK_EMPTY,
+        helpers::{create_address, keccak256},
+        spec::SpecId,
+    },
+};
+use primitive_types::U256;
+
+#[test]
+fn created_contract_has_the_hash_of_the_deployed_code() {
+    let mut ctx = context(SpecId::Cancun);
+
+    // returns 6001600055
+    let initialisation_code = hex::decode("6460016000556000526005601bf3").unwrap();
+    let tx = transaction(None, 100_000, 0, initialisation_code);
+    assert!(EVM::transact(&mut ctx, &tx).unwrap().success);
+
+    let address = create_address(SENDER, 0);
+    let code_hash = ctx.global_state[&address].code_hash;
+    assert_eq!(code_hash, keccak256(&hex::decode("6001600055").unwrap()));
+
+    // the code is shared with the code store instead of being copied
+    assert!(Arc::ptr_eq(&ctx.code(address), &ctx.code_store[&code_hash]));
+}
+
+#[test]
+fn extcodehash_tells_existing_accounts_without_code_from_missing_ones() {
+    let mut ctx = context(SpecId::Cancun);
+
+    // stores the code hash of the sender in the slot 0,
+    // the one of 0xdead in the slot 1 and its own in the slot 2
+    let code = "61aaaa3f60005561dead3f600155303f60025500";
+    install(&mut ctx, RECIPIENT, 0, code);
+
+    let tx = transaction(Some(RECIPIENT), 100_000, 0, Vec::new());
+    assert!(EVM::transact(&mut ctx, &tx).unwrap().success);
+
+    let storage = &ctx.global_state[&RECIPIENT].storage;
+    assert_eq!(storage[&U256::zero()], KECCAK_EMPTY);
+    assert_eq!(storage[&U256::one()], U256::zero());
+    assert_eq!(
+        storage[&U256::from(2)],
+        keccak256(&hex::decode(code).unwrap())
+    );
+}
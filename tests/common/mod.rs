@@ -0,0 +1,97 @@
+//! Helpers shared by the integration tests
+#![allow(dead_code)]
+
+use std::sync::Arc;
+
+use evm_from_scratch_new::evm::{
+    evm::EVM,
+    utils::{
+        database::InMemoryDatabase,
+        spec::SpecId,
+        transaction::{Transaction, TransactionType},
+        types::{AccountState, EVMReturnData, ExecutionContext, GlobalState},
+    },
+};
+use primitive_types::U256;
+
+/// Parses a hexadecimal number without the 0x prefix, such as an address or a hash
+pub fn u256(hex: &str) -> U256 {
+    U256::from_str_radix(hex, 16).unwrap()
+}
+
+pub const SENDER: U256 = U256([0xaaaa, 0, 0, 0]);
+pub const RECIPIENT: U256 = U256([0xbbbb, 0, 0, 0]);
+pub const BENEFICIARY: U256 = U256([0xcccc, 0, 0, 0]);
+
+pub const ETHER: u64 = 1_000_000_000_000_000_000;
+pub const BASE_FEE: u64 = 10;
+pub const GAS_PRICE: u64 = 12;
+
+/// A context whose world state only holds the sender, with one ether
+pub fn context(spec: SpecId) -> ExecutionContext {
+    let mut ctx = context_on(spec, InMemoryDatabase::default());
+    ctx.clear_world_state();
+
+    let mut sender = AccountState::new();
+    sender.balance = U256::from(ETHER);
+    ctx.insert_account(SENDER, sender, Vec::new());
+
+    ctx
+}
+
+/// A context which reads its world state from `database` when it is accessed
+pub fn context_on(spec: SpecId, database: InMemoryDatabase) -> ExecutionContext {
+    let mut ctx = ExecutionContext::new();
+    ctx.spec = spec;
+    ctx.global_state = GlobalState::new();
+    ctx.db = Arc::new(database);
+    ctx.input.block_header.gas_limit = U256::from(30_000_000);
+    ctx.input.block_header.base_fee = U256::from(BASE_FEE);
+    ctx.input.block_header.beneficiary = BENEFICIARY;
+
+    ctx
+}
+
+/// Creates the account at `address` with `balance` and the code given in hexadecimal
+pub fn install(ctx: &mut ExecutionContext, address: U256, balance: u64, code: &str) {
+    let mut account_state = AccountState::new();
+    account_state.balance = U256::from(balance);
+    ctx.insert_account(address, account_state, hex::decode(code).unwrap());
+}
+
+/// A legacy transaction from the sender, paying `GAS_PRICE`
+pub fn transaction(to: Option<U256>, gas_limit: u64, value: u64, data: Vec<u8>) -> Transaction {
+    Transaction {
+        sender: SENDER,
+        to,
+        nonce: 0,
+        gas_limit: U256::from(gas_limit),
+        value: U256::from(value),
+        data,
+        tx_type: TransactionType::Legacy {
+            gas_price: U256::from(GAS_PRICE),
+        },
+    }
+}
+
+/// The balance of the account at `address`, which is zero if it does not exist
+pub fn balance(ctx: &ExecutionContext, address: U256) -> U256 {
+    ctx.global_state
+        .get(&address)
+        .map(|account_state| account_state.balance)
+        .unwrap_or_default()
+}
+
+/// Runs `code`, given in hexadecimal, with `gas` as if it were the code of the
+/// recipient called by the sender, without creating the recipient
+pub fn execute(ctx: &mut ExecutionContext, code: &str, gas: u64) -> EVMReturnData {
+    ctx.input.address = RECIPIENT;
+    ctx.input.sender = SENDER;
+    ctx.input.origin = SENDER;
+    ctx.input.value = U256::zero();
+    ctx.input.data = Vec::new();
+    ctx.input.bytecode = hex::decode(code).unwrap().into();
+    ctx.machine_state.gas = U256::from(gas);
+
+    EVM::execute(ctx)
+}
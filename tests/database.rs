@@ -0,0 +1,55 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::{context_on, execute, RECIPIENT};
+use evm_from_scratch_new::evm::utils::{
+    database::InMemoryDatabase, helpers::create_address, spec::SpecId, types::AccountState,
+};
+use primitive_types::U256;
+
+/// A database holding the recipient, with a balance of 77 and a nonce of 5
+fn database() -> InMemoryDatabase {
+    let recipient = AccountState {
+        nonce: 5,
+        balance: U256::from(77),
+        ..AccountState::new()
+    };
+
+    InMemoryDatabase {
+        accounts: HashMap::from([(RECIPIENT, recipient)]),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn executing_account_is_read_from_the_database() {
+    let mut ctx = context_on(SpecId::Cancun, database());
+
+    // SELFBALANCE and CREATE without initialisation code
+    let result = execute(&mut ctx, "47600060006000f0", 100_000);
+    assert!(result.success);
+
+    let created = create_address(RECIPIENT, 5);
+    assert_eq!(ctx.machine_state.stack, [U256::from(77), created]);
+    assert_eq!(ctx.global_state[&RECIPIENT].nonce, 6);
+    assert_eq!(ctx.global_state[&RECIPIENT].balance, U256::from(77));
+}
+
+#[test]
+fn create_collides_with_storage_which_was_not_read() {
+    let mut database = database();
+    let target = AccountState {
+        storage: HashMap::from([(U256::one(), U256::one())]),
+        ..AccountState::new()
+    };
+    database
+        .accounts
+        .insert(create_address(RECIPIENT, 5), target);
+    let mut ctx = context_on(SpecId::Cancun, database);
+
+    // CREATE without initialisation code
+    let result = execute(&mut ctx, "600060006000f0", 100_000);
+    assert!(result.success);
+    assert_eq!(ctx.machine_state.stack, [U256::zero()]);
+}
@@ -0,0 +1,17 @@
+use std::process::Command;
+
+/// Runs the tests of the challenge in `tests.json`, together with the ones
+/// of `tests/fixtures/evm_tests.json` which replace those it skips
+#[test]
+fn evm_tests_pass() {
+    let output = Command::new(env!("CARGO_BIN_EXE_evm_from_scratch_new"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.ends_with("Congratulations!\n"), "{}", stdout);
+    assert_eq!(stdout.matches("SKIP").count(), 9, "{}", stdout);
+    assert!(stdout.contains("SELFDESTRUCT (London)\nPASS"), "{}", stdout);
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use common::u256;
+use evm_from_scratch_new::evm::utils::{
+    geth::{Genesis, StateDump},
+    helpers::keccak256,
+    types::ExecutionContext,
+};
+use primitive_types::U256;
+
+/// The accounts before the transaction of the LOG1 state test fixture,
+/// with the quantities written in every format geth accepts
+const GENESIS: &str = r#"{
+    "config": {"chainId": 1},
+    "alloc": {
+        "0x095e7baea6a6c7c4c2dfeb977efac326af552d87": {
+            "balance": "1000000000000000000",
+            "code": "0x60ff60005360aa60016000a1"
+        },
+        "a94f5374fce5edbc8e2a8697c15331677e6ebf0b": {
+            "balance": "0x0de0b6b3a7640000",
+            "nonce": 0
+        }
+    }
+}"#;
+
+#[test]
+fn genesis_is_loaded_with_its_code() {
+    let mut ctx = ExecutionContext::new();
+    Genesis::from_json(GENESIS).unwrap().load(&mut ctx);
+
+    assert_eq!(ctx.global_state.len(), 2);
+
+    let contract = u256("095e7baea6a6c7c4c2dfeb977efac326af552d87");
+    let code = hex::decode("60ff60005360aa60016000a1").unwrap();
+    assert_eq!(&*ctx.code(contract), code);
+    assert_eq!(ctx.global_state[&contract].code_hash, keccak256(&code));
+
+    let sender = u256("a94f5374fce5edbc8e2a8697c15331677e6ebf0b");
+    assert_eq!(
+        ctx.global_state[&sender].balance,
+        U256::from(1_000_000_000_000_000_000_u64)
+    );
+
+    assert_eq!(
+        ctx.state_root(),
+        Some(u256(
+            "1aff089b56afe5a1700264cc7ef7834d78e37ecafa43eb670853b636df2e7d75"
+        ))
+    );
+}
+
+#[test]
+fn genesis_rejects_invalid_quantities() {
+    let json = r#"{"alloc": {"0x01": {"balance": "0xzz"}}}"#;
+    assert!(Genesis::from_json(json).is_err());
+
+    let json = r#"{"alloc": {"0x01": {"balance": "1", "nonce": "0x10000000000000000"}}}"#;
+    assert!(Genesis::from_json(json).is_err());
+}
+
+#[test]
+fn dump_matches_the_format_of_geth() {
+    let genesis = r#"{
+        "alloc": {
+            "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed": {
+                "balance": 255,
+                "nonce": "0x01",
+                "code": "0x6001",
+                "storage": {
+                    "0x01": "0x0a",
+                    "0x02": "0x0100",
+                    "0x03": "0x00"
+                }
+            }
+        }
+    }"#;
+    let mut ctx = ExecutionContext::new();
+    Genesis::from_json(genesis).unwrap().load(&mut ctx);
+
+    let dump = StateDump::new(&ctx).unwrap();
+    assert_eq!(dump.root, format!("{:#066x}", ctx.state_root().unwrap()));
+
+    // the address is checksummed as in EIP-55
+    let account = &dump.accounts["0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"];
+    assert_eq!(account.balance, "255");
+    assert_eq!(account.nonce, 1);
+    assert_eq!(account.code, "0x6001");
+    assert_eq!(
+        account.code_hash,
+        format!("{:#066x}", keccak256(&[0x60, 0x01]))
+    );
+
+    // zero slots are left out, and the values are made of whole bytes
+    assert_eq!(account.storage.len(), 2);
+    assert_eq!(account.storage[&format!("{:#066x}", 1)], "0a");
+    assert_eq!(account.storage[&format!("{:#066x}", 2)], "0100");
+
+    let json: serde_json::Value = serde_json::from_str(&dump.to_json()).unwrap();
+    assert_eq!(
+        json["accounts"]["0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"]["codeHash"],
+        account.code_hash
+    );
+}
+
+#[test]
+fn dump_checksums_addresses() {
+    // the examples of EIP-55
+    let addresses = [
+        "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+        "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+        "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+        "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+    ];
+    let alloc: Vec<String> = addresses
+        .iter()
+        .map(|address| format!(r#""{}": {{"balance": "1"}}"#, address.to_lowercase()))
+        .collect();
+    let genesis = format!(r#"{{"alloc": {{{}}}}}"#, alloc.join(","));
+
+    let mut ctx = ExecutionContext::new();
+    Genesis::from_json(&genesis).unwrap().load(&mut ctx);
+    let dump = StateDump::new(&ctx).unwrap();
+
+    for address in addresses {
+        assert!(dump.accounts.contains_key(address), "{}", address);
+    }
+}
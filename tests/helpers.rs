@@ -0,0 +1,64 @@
+mod common;
+
+use common::u256;
+use evm_from_scratch_new::evm::utils::{
+    constants::KECCAK_EMPTY,
+    helpers::{create2_address, create_address, keccak256},
+};
+use primitive_types::U256;
+
+#[test]
+fn keccak256_of_known_inputs() {
+    assert_eq!(keccak256(&[]), KECCAK_EMPTY);
+    assert_eq!(
+        keccak256(&[]),
+        u256("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470")
+    );
+}
+
+#[test]
+fn create_address_of_consecutive_nonces() {
+    let sender = u256("6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0");
+
+    assert_eq!(
+        create_address(sender, 0),
+        u256("cd234a471b72ba2f1ccf0a70fcaba648a5eecd8d")
+    );
+    assert_eq!(
+        create_address(sender, 1),
+        u256("343c43a37d37dff08ae8c4a11544c718abb4fcf8")
+    );
+    assert_eq!(
+        create_address(sender, 2),
+        u256("f778b86fa74e846c4f0a1fbd1335fe81c00a0c91")
+    );
+}
+
+/// The examples of EIP-1014
+#[test]
+fn create2_address_of_the_eip_examples() {
+    assert_eq!(
+        create2_address(U256::zero(), U256::zero(), &[0x00]),
+        u256("4d1a2e2bb4f88f0250f26ffff098b0b30b26bf38")
+    );
+    assert_eq!(
+        create2_address(
+            u256("deadbeef00000000000000000000000000000000"),
+            U256::zero(),
+            &[0x00]
+        ),
+        u256("b928f69bb1d91cd65274e3c79d8986362984fda3")
+    );
+    assert_eq!(
+        create2_address(
+            u256("00000000000000000000000000000000deadbeef"),
+            U256::from(0xcafebabe_u64),
+            &hex::decode("deadbeef").unwrap()
+        ),
+        u256("60f3f640a8508fc6a86d45df051962668e1e8ac7")
+    );
+    assert_eq!(
+        create2_address(U256::zero(), U256::zero(), &[]),
+        u256("e33c0c7f7df4809055c3eba6c09cfe4baf1bd9e0")
+    );
+}
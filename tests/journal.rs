@@ -0,0 +1,76 @@
+mod common;
+
+use common::{balance, context, execute, install, RECIPIENT};
+use evm_from_scratch_new::evm::utils::{
+    helpers::create_address, spec::SpecId, types::ExecutionContext,
+};
+use primitive_types::U256;
+
+const OUTER: U256 = RECIPIENT;
+const INNER: U256 = U256([0xb000, 0, 0, 0]);
+const VICTIM: U256 = U256([0xc000, 0, 0, 0]);
+const COLD: U256 = U256([0xd000, 0, 0, 0]);
+const HEIR: U256 = U256([0xe000, 0, 0, 0]);
+
+/// Runs OUTER, which sends 1 wei to INNER. INNER emits a log, creates an
+/// account, reads the balance of COLD and calls VICTIM, which self-destructs
+/// in favour of HEIR, and then runs `ending`
+fn run(ending: &str) -> ExecutionContext {
+    let mut ctx = context(SpecId::London);
+
+    let outer = "6000600060006000600161b0005af100";
+    install(&mut ctx, OUTER, 10, outer);
+    let inner = [
+        "60006000a0",
+        "600060006000f050",
+        "61d0003150",
+        "6000600060006000600061c0005af150",
+        ending,
+    ];
+    install(&mut ctx, INNER, 0, &inner.concat());
+    install(&mut ctx, VICTIM, 3, "61e000ff");
+
+    assert!(execute(&mut ctx, outer, 1_000_000).success);
+    ctx
+}
+
+#[test]
+fn returning_call_keeps_its_changes() {
+    let ctx = run("00");
+    let created = create_address(INNER, 0);
+    let substate = &ctx.accrued_substate;
+
+    assert_eq!(balance(&ctx, OUTER), U256::from(9));
+    assert_eq!(balance(&ctx, INNER), U256::one());
+    assert!(ctx.global_state.contains_key(&created));
+    assert_eq!(substate.logs.len(), 1);
+    assert!(substate.accessed_accounts.contains(&COLD));
+    assert!(substate.touched_accounts.contains(&HEIR));
+    assert!(substate.self_destruct_set.contains(&VICTIM));
+    assert_eq!(balance(&ctx, HEIR), U256::from(3));
+}
+
+#[test]
+fn reverted_call_undoes_its_changes_and_those_of_its_sub_calls() {
+    let ctx = run("60006000fd");
+    let created = create_address(INNER, 0);
+    let substate = &ctx.accrued_substate;
+
+    // the value sent by OUTER goes back to it
+    assert_eq!(balance(&ctx, OUTER), U256::from(10));
+    assert_eq!(balance(&ctx, INNER), U256::zero());
+    assert_eq!(ctx.global_state[&INNER].nonce, 0);
+
+    assert!(!ctx.global_state.contains_key(&created));
+    assert!(substate.logs.is_empty());
+    assert!(!substate.accessed_accounts.contains(&COLD));
+    assert!(!substate.accessed_accounts.contains(&created));
+    assert!(!substate.touched_accounts.contains(&HEIR));
+    assert!(!substate.touched_accounts.contains(&VICTIM));
+    assert!(substate.self_destruct_set.is_empty());
+    assert_eq!(balance(&ctx, VICTIM), U256::from(3));
+    assert!(!ctx.global_state.contains_key(&HEIR));
+
+    // the call to INNER itself is not reverted, so INNER stays warm
+    assert!(substate.accessed_accounts.contains(&INNER));
+}
@@ -0,0 +1,221 @@
+use evm_from_scratch_new::evm::{
+    precompiles::{
+        blake2f::blake2f, bn254::ecadd, ecrecover::ecrecover, modexp::modexp,
+        point_evaluation::point_evaluation,
+    },
+    utils::spec::SpecId,
+};
+use primitive_types::U256;
+use sha2::{Digest, Sha256};
+
+/// A signature of geth's tests, whose signer is 0xceaccac640adf55b2028469bd36ba501f28b699d
+const ECRECOVER_INPUT: &str = "38d18acb67d25c8bb9942764b62f18e17054f66a817bd4295423adf9ed98873e\
+                               000000000000000000000000000000000000000000000000000000000000001b\
+                               38d18acb67d25c8bb9942764b62f18e17054f66a817bd4295423adf9ed98873e\
+                               789d1dd423d25f0772d2748d60f7e4b81bb14d086eba8e8e8efb6dcff8a4ae02";
+
+const ECRECOVER_OUTPUT: &str = "000000000000000000000000ceaccac640adf55b2028469bd36ba501f28b699d";
+
+fn gas(gas: u64) -> U256 {
+    U256::from(gas)
+}
+
+#[test]
+fn ecrecover_recovers_the_signer() {
+    let result = ecrecover(&hex::decode(ECRECOVER_INPUT).unwrap(), gas(3000)).unwrap();
+    assert_eq!(hex::encode(result.output), ECRECOVER_OUTPUT);
+    assert_eq!(result.gas_used, gas(3000));
+
+    assert!(ecrecover(&hex::decode(ECRECOVER_INPUT).unwrap(), gas(2999)).is_none());
+}
+
+#[test]
+fn ecrecover_accepts_a_high_s() {
+    // the same signature with `s` replaced by `n - s` and the opposite `v`
+    let mut input = hex::decode(ECRECOVER_INPUT).unwrap();
+    input[63] = 0x1c;
+    input[96..].copy_from_slice(
+        &hex::decode("8762e22bdc2da0f88d2d8b729f081b469efd8fde408e11ad30d6f0bcd791933f").unwrap(),
+    );
+
+    let result = ecrecover(&input, gas(3000)).unwrap();
+    assert_eq!(hex::encode(result.output), ECRECOVER_OUTPUT);
+}
+
+#[test]
+fn ecrecover_returns_nothing_for_an_invalid_signature() {
+    let mut input = hex::decode(ECRECOVER_INPUT).unwrap();
+    input[63] = 0x1d;
+
+    let result = ecrecover(&input, gas(3000)).unwrap();
+    assert!(result.output.is_empty());
+    assert_eq!(result.gas_used, gas(3000));
+}
+
+#[test]
+fn ecadd_adds_points() {
+    let input = hex::decode(
+        "18b18acfb4c2c30276db5411368e7185b311dd124691610c5d3b74034e093dc9\
+         063c909c4720840cb5134cb9f59fa749755796819658d32efc0d288198f37266\
+         07c2b7f58a84bd6145f00c9c2bc0bb1a187f20ff2c92963a88019e7c6a014eed\
+         06614e20c147e940f2d70da3f74c9a17df361706a4485c742bd6788478fa17d7",
+    )
+    .unwrap();
+
+    let result = ecadd(&input, gas(150), SpecId::Istanbul).unwrap();
+    assert_eq!(
+        hex::encode(result.output),
+        "2243525c5efd4b9c3d3c45ac0ca3fe4dd85e830a4ce6b65fa1eeaee202839703\
+         301d1d33be6da8e509df21cc35964723180eed7532537db9ae5e7d48f195c915"
+    );
+    assert_eq!(result.gas_used, gas(150));
+
+    // the cost was lowered in Istanbul (EIP-1108)
+    assert!(ecadd(&input, gas(150), SpecId::Byzantium).is_none());
+    assert_eq!(
+        ecadd(&input, gas(500), SpecId::Byzantium).unwrap().gas_used,
+        gas(500)
+    );
+}
+
+#[test]
+fn ecadd_doubles_the_generator() {
+    // (1, 2) + (1, 2)
+    let mut input = vec![0; 128];
+    input[31] = 1;
+    input[63] = 2;
+    input[95] = 1;
+    input[127] = 2;
+
+    let result = ecadd(&input, gas(150), SpecId::Istanbul).unwrap();
+    assert_eq!(
+        hex::encode(result.output),
+        "030644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd3\
+         15ed738c0e0a7c92e7845f96b2ae9c0a68a6a449e3538fc7ff3ebf7a5a18a2c4"
+    );
+}
+
+#[test]
+fn ecadd_rejects_a_point_off_the_curve() {
+    let mut input = vec![0; 128];
+    input[31] = 1;
+    input[63] = 3;
+
+    assert!(ecadd(&input, gas(150), SpecId::Istanbul).is_none());
+}
+
+/// The first example of EIP-198: `3 ^ (p - 1) % p` is 1 by Fermat's little theorem
+#[test]
+fn modexp_of_the_eip_example() {
+    let input = hex::decode(
+        "0000000000000000000000000000000000000000000000000000000000000001\
+         0000000000000000000000000000000000000000000000000000000000000020\
+         0000000000000000000000000000000000000000000000000000000000000020\
+         03\
+         fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2e\
+         fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f",
+    )
+    .unwrap();
+    let one = format!("{:064x}", 1);
+
+    let result = modexp(&input, gas(13056), SpecId::Byzantium).unwrap();
+    assert_eq!(hex::encode(result.output), one);
+    assert_eq!(result.gas_used, gas(13056));
+
+    // repriced in Berlin (EIP-2565)
+    let result = modexp(&input, gas(1360), SpecId::Berlin).unwrap();
+    assert_eq!(hex::encode(result.output), one);
+    assert_eq!(result.gas_used, gas(1360));
+    assert!(modexp(&input, gas(1359), SpecId::Berlin).is_none());
+}
+
+#[test]
+fn modexp_with_an_empty_modulus_returns_nothing() {
+    let input = hex::decode(
+        "0000000000000000000000000000000000000000000000000000000000000001\
+         0000000000000000000000000000000000000000000000000000000000000001\
+         0000000000000000000000000000000000000000000000000000000000000000\
+         0203",
+    )
+    .unwrap();
+
+    let result = modexp(&input, gas(200), SpecId::Berlin).unwrap();
+    assert!(result.output.is_empty());
+    assert_eq!(result.gas_used, gas(200));
+}
+
+/// The compression of the only block of "abc" with 12 rounds, which
+/// is the BLAKE2b-512 hash of "abc" (the fifth example of EIP-152)
+#[test]
+fn blake2f_hashes_abc() {
+    let mut input = hex::decode(
+        "0000000c\
+         48c9bdf267e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5\
+         d182e6ad7f520e511f6c3e2b8c68059b6bbd41fbabd9831f79217e1319cde05b\
+         616263",
+    )
+    .unwrap();
+    input.resize(4 + 64 + 128, 0);
+    input.extend_from_slice(&hex::decode("03000000000000000000000000000000").unwrap());
+    input.push(1);
+
+    let result = blake2f(&input, gas(12)).unwrap();
+    assert_eq!(
+        hex::encode(result.output),
+        "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+         7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"
+    );
+    assert_eq!(result.gas_used, gas(12));
+    assert!(blake2f(&input, gas(11)).is_none());
+
+    // the final block flag is either 0 or 1
+    input[212] = 2;
+    assert!(blake2f(&input, gas(12)).is_none());
+
+    // the input is exactly 213 bytes long
+    assert!(blake2f(&input[..212], gas(12)).is_none());
+}
+
+/// A proof of revm's tests that the blob committed to by `commitment`
+/// evaluates to `y` at `z`, which is the modulus of the field minus one
+#[test]
+fn point_evaluation_verifies_a_proof() {
+    let commitment = hex::decode(
+        "8f59a8d2a1a625a17f3fea0fe5eb8c896db3764f3185481bc22f91b4aaffcca2\
+         5f26936857bc3a7c2539ea8ec3a952b7",
+    )
+    .unwrap();
+    let mut versioned_hash = Sha256::digest(&commitment).to_vec();
+    versioned_hash[0] = 0x01;
+
+    let mut input = [
+        versioned_hash,
+        hex::decode("73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000000").unwrap(),
+        hex::decode("1522a4a7f34e1ea350ae07c29c96c7e79655aa926122e95fe69fcbd932ca49e9").unwrap(),
+        commitment,
+        hex::decode(
+            "a62ad71d14c5719385c0686f1871430475bf3a00f0aa3f7b8dd99a9abc216074\
+             4faf0070725e00b60ad9a026a15b1a8c",
+        )
+        .unwrap(),
+    ]
+    .concat();
+
+    let result = point_evaluation(&input, gas(50_000)).unwrap();
+    assert_eq!(
+        hex::encode(result.output),
+        "0000000000000000000000000000000000000000000000000000000000001000\
+         73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001"
+    );
+    assert_eq!(result.gas_used, gas(50_000));
+    assert!(point_evaluation(&input, gas(49_999)).is_none());
+
+    // the proof does not hold for another value
+    input[95] ^= 1;
+    assert!(point_evaluation(&input, gas(50_000)).is_none());
+    input[95] ^= 1;
+
+    // a versioned hash which is not the one of the commitment
+    input[31] ^= 1;
+    assert!(point_evaluation(&input, gas(50_000)).is_none());
+}
@@ -0,0 +1,73 @@
+use evm_from_scratch_new::evm::utils::rlp::{
+    encode_address, encode_bytes, encode_hash, encode_list, encode_u256,
+};
+use primitive_types::U256;
+
+#[test]
+fn encode_bytes_with_short_and_long_prefixes() {
+    assert_eq!(encode_bytes(b""), [0x80]);
+    assert_eq!(encode_bytes(&[0x00]), [0x00]);
+    assert_eq!(encode_bytes(&[0x7f]), [0x7f]);
+    assert_eq!(encode_bytes(&[0x80]), [0x81, 0x80]);
+    assert_eq!(encode_bytes(b"dog"), hex::decode("83646f67").unwrap());
+
+    let lorem = b"Lorem ipsum dolor sit amet, consectetur adipisicing elit";
+    assert_eq!(encode_bytes(lorem), [&[0xb8, 0x38][..], lorem].concat());
+
+    let long = vec![0xaa; 1024];
+    assert_eq!(
+        encode_bytes(&long),
+        [&[0xb9, 0x04, 0x00][..], &long].concat()
+    );
+}
+
+#[test]
+fn encode_u256_without_leading_zeros() {
+    assert_eq!(encode_u256(U256::zero()), [0x80]);
+    assert_eq!(encode_u256(U256::from(15)), [0x0f]);
+    assert_eq!(encode_u256(U256::from(1024)), [0x82, 0x04, 0x00]);
+    assert_eq!(
+        encode_u256(U256::MAX),
+        [vec![0xa0], vec![0xff; 32]].concat()
+    );
+}
+
+#[test]
+fn encode_address_and_hash_with_fixed_length() {
+    assert_eq!(
+        encode_address(U256::zero()),
+        [vec![0x94], vec![0; 20]].concat()
+    );
+    assert_eq!(
+        encode_address(U256::from(0x01)),
+        [vec![0x94], vec![0; 19], vec![0x01]].concat()
+    );
+    assert_eq!(
+        encode_hash(U256::zero()),
+        [vec![0xa0], vec![0; 32]].concat()
+    );
+}
+
+#[test]
+fn encode_nested_lists() {
+    assert_eq!(encode_list(&[]), [0xc0]);
+    assert_eq!(
+        encode_list(&[encode_bytes(b"cat"), encode_bytes(b"dog")]),
+        hex::decode("c88363617483646f67").unwrap()
+    );
+
+    // the set theoretical representation of three: [ [], [[]], [ [], [[]] ] ]
+    let zero = encode_list(&[]);
+    let one = encode_list(std::slice::from_ref(&zero));
+    let two = encode_list(&[zero.clone(), one.clone()]);
+    assert_eq!(
+        encode_list(&[zero, one, two]),
+        hex::decode("c7c0c1c0c3c0c1c0").unwrap()
+    );
+
+    let items = vec![encode_bytes(b"dog"); 14];
+    assert_eq!(
+        encode_list(&items),
+        [vec![0xf8, 56], items.concat()].concat()
+    );
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use common::{balance, context, install, transaction, u256, RECIPIENT, SENDER};
+use evm_from_scratch_new::evm::{
+    evm::EVM,
+    utils::{helpers::create_address, spec::SpecId},
+};
+use primitive_types::U256;
+
+#[test]
+fn self_destruct_deletes_the_account_before_cancun() {
+    // sends the balance to 0xa1c3 and self-destructs
+    let code = "73a1c300000000000000000000000000000000a1c3ff";
+    let heir = u256("a1c300000000000000000000000000000000a1c3");
+
+    let mut ctx = context(SpecId::London);
+    install(&mut ctx, RECIPIENT, 7, code);
+    let tx = transaction(Some(RECIPIENT), 100_000, 0, Vec::new());
+
+    assert!(EVM::transact(&mut ctx, &tx).unwrap().success);
+    assert!(!ctx.global_state.contains_key(&RECIPIENT));
+    assert_eq!(balance(&ctx, heir), U256::from(7));
+
+    // from Cancun, only the balance is sent, unless the account
+    // was created in the same transaction (EIP-6780)
+    let mut ctx = context(SpecId::Cancun);
+    install(&mut ctx, RECIPIENT, 7, code);
+
+    assert!(EVM::transact(&mut ctx, &tx).unwrap().success);
+    assert_eq!(balance(&ctx, RECIPIENT), U256::zero());
+    assert_eq!(&*ctx.code(RECIPIENT), hex::decode(code).unwrap());
+    assert_eq!(balance(&ctx, heir), U256::from(7));
+}
+
+#[test]
+fn self_destruct_deletes_an_account_created_in_the_same_transaction() {
+    // the initialisation code sends the value to 0xa1c3 and self-destructs
+    let initialisation_code = hex::decode("73a1c300000000000000000000000000000000a1c3ff").unwrap();
+    let heir = u256("a1c300000000000000000000000000000000a1c3");
+
+    let mut ctx = context(SpecId::Cancun);
+    let tx = transaction(None, 100_000, 7, initialisation_code);
+
+    let result = EVM::transact(&mut ctx, &tx).unwrap();
+    assert!(result.success);
+    assert!(!ctx.global_state.contains_key(&create_address(SENDER, 0)));
+    assert_eq!(balance(&ctx, heir), U256::from(7));
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::{context, context_on, install, SENDER};
+use evm_from_scratch_new::evm::utils::{
+    database::InMemoryDatabase, snapshot::StateSnapshot, spec::SpecId, types::ExecutionContext,
+};
+use primitive_types::U256;
+
+const CONTRACT: U256 = U256([0xc0de, 0, 0, 0]);
+
+/// A context whose world state holds a contract with storage
+/// and the sender, with a balance which takes all of its bits
+fn context_with_contract() -> ExecutionContext {
+    let mut ctx = context(SpecId::Cancun);
+    ctx.global_state.get_mut(&SENDER).unwrap().balance = U256::MAX;
+
+    install(&mut ctx, CONTRACT, 10, "6001600055");
+    let contract = ctx.global_state.get_mut(&CONTRACT).unwrap();
+    contract.nonce = 1;
+    contract.storage = HashMap::from([(U256::zero(), U256::from(0x2a)), (U256::MAX, U256::one())]);
+
+    ctx
+}
+
+#[test]
+fn snapshot_survives_json_and_binary() {
+    let snapshot = StateSnapshot::new(&context_with_contract()).unwrap();
+    assert_eq!(snapshot.accounts.len(), 2);
+    assert_eq!(snapshot.code.len(), 1);
+
+    let json = snapshot.to_json();
+    assert_eq!(StateSnapshot::from_json(&json).unwrap(), snapshot);
+
+    let binary = snapshot.to_binary();
+    assert_eq!(StateSnapshot::from_binary(&binary).unwrap(), snapshot);
+    assert!(binary.len() < json.len());
+}
+
+#[test]
+fn snapshot_rejects_invalid_input() {
+    assert!(StateSnapshot::from_json(r#"{"accounts": {"0xzz": {}}, "code": {}}"#).is_err());
+    assert!(StateSnapshot::from_binary(&[0xff; 8]).is_err());
+
+    // code with an odd number of hexadecimal digits
+    assert!(StateSnapshot::from_json(r#"{"accounts": {}, "code": {"0x01": "0xabc"}}"#).is_err());
+    assert!(StateSnapshot::from_json(r#"{"accounts": {}, "code": {"0x01": "0xabcd"}}"#).is_ok());
+}
+
+#[test]
+fn loading_a_snapshot_without_the_code_of_an_account_fails() {
+    let mut snapshot = StateSnapshot::new(&context_with_contract()).unwrap();
+    snapshot.code.clear();
+
+    let mut ctx = context(SpecId::Cancun);
+    let state_root = ctx.state_root();
+    assert!(snapshot.load(&mut ctx).is_err());
+    assert_eq!(ctx.state_root(), state_root);
+}
+
+#[test]
+fn loading_a_snapshot_restores_the_world_state() {
+    let ctx = context_with_contract();
+    let snapshot = StateSnapshot::new(&ctx).unwrap();
+
+    let mut loaded = ExecutionContext::new();
+    snapshot.load(&mut loaded).unwrap();
+
+    assert_eq!(loaded.state_root(), ctx.state_root());
+    assert_eq!(&*loaded.code(CONTRACT), hex::decode("6001600055").unwrap());
+    assert_eq!(
+        loaded.global_state[&CONTRACT].storage[&U256::MAX],
+        U256::one()
+    );
+    assert_eq!(StateSnapshot::new(&loaded).unwrap(), snapshot);
+}
+
+#[test]
+fn snapshot_holds_the_accounts_of_the_database() {
+    let snapshot = StateSnapshot::new(&context_with_contract()).unwrap();
+
+    // nothing was read from the database yet
+    let ctx = context_on(SpecId::Cancun, InMemoryDatabase::from(&snapshot));
+    assert!(ctx.global_state.is_empty());
+    assert_eq!(StateSnapshot::new(&ctx).unwrap(), snapshot);
+    assert_eq!(ctx.state_root(), context_with_contract().state_root());
+}
+
+#[test]
+fn loading_a_snapshot_stops_reading_the_database() {
+    let snapshot = StateSnapshot::new(&context_with_contract()).unwrap();
+    let mut ctx = context_on(SpecId::Cancun, InMemoryDatabase::from(&snapshot));
+
+    StateSnapshot::default().load(&mut ctx).unwrap();
+    ctx.load_account(CONTRACT);
+    ctx.load_storage(CONTRACT, U256::zero());
+
+    assert!(ctx.global_state.is_empty());
+    assert_eq!(StateSnapshot::new(&ctx).unwrap(), StateSnapshot::default());
+}
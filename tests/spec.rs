@@ -0,0 +1,52 @@
+mod common;
+
+use common::{context, execute};
+use evm_from_scratch_new::evm::utils::{helpers::get_opcodes, spec::SpecId};
+
+#[test]
+fn opcodes_are_only_available_from_the_fork_which_introduced_them() {
+    let frontier = get_opcodes(SpecId::Frontier);
+    assert!(!frontier.contains_key(&0xf4));
+
+    let istanbul = get_opcodes(SpecId::Istanbul);
+    assert!(istanbul.contains_key(&0x47));
+    for opcode in [0x48, 0x49, 0x4a, 0x5c, 0x5d, 0x5e, 0x5f] {
+        assert!(!istanbul.contains_key(&opcode), "{:#x}", opcode);
+    }
+
+    let cancun = get_opcodes(SpecId::Cancun);
+    for opcode in [0x48, 0x49, 0x4a, 0x5c, 0x5d, 0x5e, 0x5f, 0xf4] {
+        assert!(cancun.contains_key(&opcode), "{:#x}", opcode);
+    }
+}
+
+#[test]
+fn opcode_of_a_later_fork_is_an_exceptional_halt() {
+    // PUSH0
+    let mut ctx = context(SpecId::Istanbul);
+    assert!(!execute(&mut ctx, "5f", 100_000).success);
+
+    let mut ctx = context(SpecId::Shanghai);
+    assert!(execute(&mut ctx, "5f", 100_000).success);
+}
+
+#[test]
+fn fork_names_of_ethereum_tests() {
+    assert_eq!(
+        SpecId::from_fork_name("EIP150"),
+        Some(SpecId::TangerineWhistle)
+    );
+    assert_eq!(
+        SpecId::from_fork_name("EIP158"),
+        Some(SpecId::SpuriousDragon)
+    );
+    assert_eq!(
+        SpecId::from_fork_name("ConstantinopleFix"),
+        Some(SpecId::Petersburg)
+    );
+    assert_eq!(SpecId::from_fork_name("Merge"), Some(SpecId::Paris));
+    assert_eq!(SpecId::from_fork_name("Cancun"), Some(SpecId::Cancun));
+
+    assert_eq!(SpecId::from_fork_name("Constantinople"), None);
+    assert_eq!(SpecId::from_fork_name("Prague"), None);
+}
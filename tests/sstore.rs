@@ -0,0 +1,126 @@
+mod common;
+
+use common::{context, execute, install, RECIPIENT};
+use evm_from_scratch_new::evm::utils::spec::SpecId;
+use primitive_types::U256;
+
+/// Runs `code`, which writes to the slot 0 whose value is `original` at the
+/// beginning of the transaction, and returns the gas used and the refund
+fn run(spec: SpecId, original: u64, code: &str, warm: bool) -> (u64, u64) {
+    let mut ctx = context(spec);
+    install(&mut ctx, RECIPIENT, 0, "");
+    ctx.global_state
+        .get_mut(&RECIPIENT)
+        .unwrap()
+        .storage
+        .insert(U256::zero(), U256::from(original));
+    if warm {
+        ctx.warm_storage(RECIPIENT, U256::zero());
+    }
+
+    let result = execute(&mut ctx, code, 100_000);
+    assert!(result.success, "{}", code);
+
+    (
+        result.gas_used.as_u64(),
+        ctx.accrued_substate.refund_balance.as_u64(),
+    )
+}
+
+/// The code, the original value, the gas used and the refund
+type Case = (&'static str, u64, u64, u64);
+
+fn check(spec: SpecId, warm: bool, cases: &[Case]) {
+    for (code, original, gas_used, refund) in cases {
+        assert_eq!(
+            run(spec, *original, code, warm),
+            (*gas_used, *refund),
+            "{} with original value {} under {:?}",
+            code,
+            original,
+            spec
+        );
+    }
+}
+
+/// The test cases of EIP-2200
+#[test]
+fn net_gas_metering_in_istanbul() {
+    check(
+        SpecId::Istanbul,
+        false,
+        &[
+            ("60006000556000600055", 0, 1612, 0),
+            ("60006000556001600055", 0, 20812, 0),
+            ("60016000556000600055", 0, 20812, 19200),
+            ("60016000556002600055", 0, 20812, 0),
+            ("60016000556001600055", 0, 20812, 0),
+            ("60006000556000600055", 1, 5812, 15000),
+            ("60006000556001600055", 1, 5812, 4200),
+            ("60006000556002600055", 1, 5812, 0),
+            ("60026000556000600055", 1, 5812, 15000),
+            ("60026000556003600055", 1, 5812, 0),
+            ("60026000556001600055", 1, 5812, 4200),
+            ("60026000556002600055", 1, 5812, 0),
+            ("60016000556000600055", 1, 5812, 15000),
+            ("60016000556002600055", 1, 5812, 0),
+            ("60016000556001600055", 1, 1612, 0),
+            ("600160005560006000556001600055", 0, 40818, 19200),
+            ("600060005560016000556000600055", 1, 10818, 19200),
+        ],
+    );
+}
+
+/// The test cases of EIP-3529, where the slot is already warm
+#[test]
+fn net_gas_metering_with_reduced_refunds_in_london() {
+    check(
+        SpecId::London,
+        true,
+        &[
+            ("60006000556000600055", 0, 212, 0),
+            ("60006000556001600055", 0, 20112, 0),
+            ("60016000556000600055", 0, 20112, 19900),
+            ("60016000556002600055", 0, 20112, 0),
+            ("60016000556001600055", 0, 20112, 0),
+            ("60006000556000600055", 1, 3012, 4800),
+            ("60006000556001600055", 1, 3012, 2800),
+            ("60006000556002600055", 1, 3012, 0),
+            ("60026000556000600055", 1, 3012, 4800),
+            ("60026000556003600055", 1, 3012, 0),
+            ("60026000556001600055", 1, 3012, 2800),
+            ("60026000556002600055", 1, 3012, 0),
+            ("60016000556000600055", 1, 3012, 4800),
+            ("60016000556002600055", 1, 3012, 0),
+            ("60016000556001600055", 1, 212, 0),
+            ("600160005560006000556001600055", 0, 40118, 19900),
+            ("600060005560016000556000600055", 1, 5918, 7600),
+        ],
+    );
+}
+
+#[test]
+fn cold_slot_costs_extra_from_berlin() {
+    // the first access pays for the cold slot (EIP-2929)
+    check(
+        SpecId::Berlin,
+        false,
+        &[
+            ("60006000556000600055", 0, 2312, 0),
+            ("60016000556000600055", 0, 22212, 19900),
+        ],
+    );
+}
+
+#[test]
+fn no_net_gas_metering_in_petersburg() {
+    check(
+        SpecId::Petersburg,
+        false,
+        &[
+            ("60006000556000600055", 0, 10012, 0),
+            ("60016000556000600055", 0, 25012, 15000),
+            ("60006000556001600055", 1, 25012, 15000),
+        ],
+    );
+}
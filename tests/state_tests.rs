@@ -0,0 +1,41 @@
+use std::process::Command;
+
+/// Runs the state test runner on the fixtures of `tests/fixtures/state_tests`,
+/// whose expected state roots and logs hashes were computed by hand
+#[test]
+fn state_tests_pass() {
+    let output = Command::new(env!("CARGO_BIN_EXE_state_tests"))
+        .arg(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/state_tests"
+        ))
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(output.status.success(), "{}", stdout);
+    assert!(!stdout.contains("FAIL"), "{}", stdout);
+    assert!(stdout.contains("Cancun: 3/3 passed"), "{}", stdout);
+    assert!(stdout.contains("Frontier: 2/2 passed"), "{}", stdout);
+    assert!(
+        stdout.contains("Constantinople: 1 skipped, fork not supported"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn state_tests_only_run_the_given_fork() {
+    let output = Command::new(env!("CARGO_BIN_EXE_state_tests"))
+        .arg(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/state_tests"
+        ))
+        .arg("Frontier")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.contains("Frontier: 2/2 passed"), "{}", stdout);
+    assert!(!stdout.contains("Cancun"), "{}", stdout);
+}
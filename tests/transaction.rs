@@ -0,0 +1,230 @@
+mod common;
+
+use common::{
+    balance, context, install, transaction, BASE_FEE, BENEFICIARY, ETHER, GAS_PRICE, RECIPIENT,
+    SENDER,
+};
+use evm_from_scratch_new::evm::{
+    evm::EVM,
+    utils::{
+        errors::{EVMError, InvalidTransaction},
+        helpers::create_address,
+        spec::SpecId,
+        transaction::{Transaction, TransactionType},
+    },
+};
+use primitive_types::U256;
+
+#[test]
+fn transfer_pays_the_intrinsic_gas() {
+    let mut ctx = context(SpecId::London);
+    let tx = transaction(Some(RECIPIENT), 21_000, 1_000, Vec::new());
+
+    let result = EVM::transact(&mut ctx, &tx).unwrap();
+    assert!(result.success);
+    assert_eq!(result.gas_used, U256::from(21_000));
+
+    // the base fee is burnt and the priority fee goes to the beneficiary
+    assert_eq!(
+        balance(&ctx, SENDER),
+        U256::from(ETHER - 21_000 * GAS_PRICE - 1_000)
+    );
+    assert_eq!(balance(&ctx, RECIPIENT), U256::from(1_000));
+    assert_eq!(
+        balance(&ctx, BENEFICIARY),
+        U256::from(21_000 * (GAS_PRICE - BASE_FEE))
+    );
+    assert_eq!(ctx.global_state[&SENDER].nonce, 1);
+}
+
+#[test]
+fn intrinsic_gas_of_data_creation_and_access_list() {
+    let data = vec![0x00, 0x01, 0x00, 0x02];
+
+    let tx = transaction(Some(RECIPIENT), 0, 0, data.clone());
+    assert_eq!(
+        tx.intrinsic_gas(SpecId::Frontier),
+        U256::from(21_000 + 2 * 4 + 2 * 68)
+    );
+    assert_eq!(
+        tx.intrinsic_gas(SpecId::Istanbul),
+        U256::from(21_000 + 2 * 4 + 2 * 16)
+    );
+
+    // a contract creation costs 32000 more from Homestead,
+    // and 2 more per word of initialisation code from Shanghai
+    let tx = transaction(None, 0, 0, data);
+    assert_eq!(
+        tx.intrinsic_gas(SpecId::Frontier),
+        U256::from(21_000 + 2 * 4 + 2 * 68)
+    );
+    assert_eq!(
+        tx.intrinsic_gas(SpecId::London),
+        U256::from(53_000 + 2 * 4 + 2 * 16)
+    );
+    assert_eq!(
+        tx.intrinsic_gas(SpecId::Shanghai),
+        U256::from(53_000 + 2 * 4 + 2 * 16 + 2)
+    );
+
+    let tx = Transaction {
+        tx_type: TransactionType::AccessList {
+            gas_price: U256::from(GAS_PRICE),
+            access_list: vec![(RECIPIENT, vec![U256::zero(), U256::one()])],
+        },
+        ..transaction(Some(RECIPIENT), 0, 0, Vec::new())
+    };
+    assert_eq!(
+        tx.intrinsic_gas(SpecId::Berlin),
+        U256::from(21_000 + 2400 + 2 * 1900)
+    );
+}
+
+#[test]
+fn invalid_transaction_leaves_the_state_untouched() {
+    let mut ctx = context(SpecId::London);
+    let state_root = ctx.state_root();
+
+    let tx = Transaction {
+        nonce: 1,
+        ..transaction(Some(RECIPIENT), 21_000, 0, Vec::new())
+    };
+    assert!(matches!(
+        EVM::transact(&mut ctx, &tx),
+        Err(EVMError::InvalidTransactionError(
+            InvalidTransaction::NonceMismatch(0, 1)
+        ))
+    ));
+
+    let tx = transaction(Some(RECIPIENT), 21_000, ETHER, Vec::new());
+    assert!(matches!(
+        EVM::transact(&mut ctx, &tx),
+        Err(EVMError::InvalidTransactionError(
+            InvalidTransaction::InsufficientFunds(..)
+        ))
+    ));
+
+    let tx = transaction(Some(RECIPIENT), 20_999, 0, Vec::new());
+    assert!(matches!(
+        EVM::transact(&mut ctx, &tx),
+        Err(EVMError::InvalidTransactionError(
+            InvalidTransaction::IntrinsicGasTooLow(..)
+        ))
+    ));
+
+    let tx = Transaction {
+        tx_type: TransactionType::Legacy {
+            gas_price: U256::from(BASE_FEE - 1),
+        },
+        ..transaction(Some(RECIPIENT), 21_000, 0, Vec::new())
+    };
+    assert!(matches!(
+        EVM::transact(&mut ctx, &tx),
+        Err(EVMError::InvalidTransactionError(
+            InvalidTransaction::FeeBelowBaseFee(..)
+        ))
+    ));
+
+    assert_eq!(ctx.state_root(), state_root);
+}
+
+#[test]
+fn sender_with_the_largest_nonce_cannot_send_transactions() {
+    let mut ctx = context(SpecId::London);
+    ctx.global_state.get_mut(&SENDER).unwrap().nonce = u64::MAX as usize;
+
+    let tx = Transaction {
+        nonce: u64::MAX as usize,
+        ..transaction(Some(RECIPIENT), 21_000, 0, Vec::new())
+    };
+    assert!(matches!(
+        EVM::transact(&mut ctx, &tx),
+        Err(EVMError::InvalidTransactionError(
+            InvalidTransaction::NonceOverflow
+        ))
+    ));
+}
+
+#[test]
+fn failed_execution_consumes_the_gas_and_keeps_the_nonce() {
+    let mut ctx = context(SpecId::Cancun);
+    install(&mut ctx, RECIPIENT, 0, "fe");
+
+    let tx = transaction(Some(RECIPIENT), 50_000, 1_000, Vec::new());
+    let result = EVM::transact(&mut ctx, &tx).unwrap();
+
+    assert!(!result.success);
+    assert_eq!(result.gas_used, U256::from(50_000));
+    assert_eq!(ctx.global_state[&SENDER].nonce, 1);
+    assert_eq!(
+        balance(&ctx, SENDER),
+        U256::from(ETHER - 50_000 * GAS_PRICE)
+    );
+    assert_eq!(balance(&ctx, RECIPIENT), U256::zero());
+}
+
+#[test]
+fn refund_is_capped_by_the_gas_used() {
+    // clears the slot 0: PUSH1 0, PUSH1 0, SSTORE
+    let run = |spec| {
+        let mut ctx = context(spec);
+        install(&mut ctx, RECIPIENT, 0, "6000600055");
+        ctx.global_state
+            .get_mut(&RECIPIENT)
+            .unwrap()
+            .storage
+            .insert(U256::zero(), U256::one());
+
+        let tx = transaction(Some(RECIPIENT), 100_000, 0, Vec::new());
+        EVM::transact(&mut ctx, &tx).unwrap().gas_used
+    };
+
+    // 21000 + 6 for the pushes, 2100 for the cold slot and 2900 for the write,
+    // of which the refund of 4800 is below the cap of a fifth (EIP-3529)
+    assert_eq!(run(SpecId::London), U256::from(26_006 - 4_800));
+
+    // before London the refund of 15000 is capped to a half
+    assert_eq!(run(SpecId::Berlin), U256::from(26_006 - 26_006 / 2));
+}
+
+#[test]
+fn contract_creation_deploys_the_returned_code() {
+    let mut ctx = context(SpecId::Cancun);
+
+    // returns 6001600055, which stores 1 in the slot 0
+    let initialisation_code = hex::decode("6460016000556000526005601bf3").unwrap();
+    let tx = transaction(None, 100_000, 5, initialisation_code);
+
+    let result = EVM::transact(&mut ctx, &tx).unwrap();
+    assert!(result.success);
+    assert!(result.output.is_empty());
+
+    let address = create_address(SENDER, 0);
+    assert_eq!(result.contract_address, Some(address));
+    assert_eq!(&*ctx.code(address), hex::decode("6001600055").unwrap());
+    assert_eq!(balance(&ctx, address), U256::from(5));
+    assert_eq!(ctx.global_state[&address].nonce, 1);
+}
+
+#[test]
+fn reverted_contract_creation_returns_the_revert_data() {
+    let mut ctx = context(SpecId::Cancun);
+
+    // reverts with the word 0x2a
+    let initialisation_code = hex::decode("602a60005260206000fd").unwrap();
+    let tx = transaction(None, 100_000, 5, initialisation_code);
+
+    let result = EVM::transact(&mut ctx, &tx).unwrap();
+    assert!(!result.success);
+
+    let mut word = [0; 32];
+    word[31] = 0x2a;
+    assert_eq!(result.output, word);
+    assert!(!ctx.global_state.contains_key(&create_address(SENDER, 0)));
+
+    // the value goes back to the sender, who only pays for the gas
+    assert_eq!(
+        balance(&ctx, SENDER),
+        U256::from(ETHER) - result.gas_used * U256::from(GAS_PRICE)
+    );
+}
@@ -0,0 +1,119 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::u256;
+use evm_from_scratch_new::evm::utils::{
+    constants::{EMPTY_TRIE_ROOT, KECCAK_EMPTY},
+    helpers::keccak256,
+    rlp,
+    trie::{state_root, storage_root, trie_root},
+    types::{AccountState, GlobalState},
+};
+use primitive_types::U256;
+
+fn items(pairs: &[(&str, &str)]) -> Vec<(Vec<u8>, Vec<u8>)> {
+    pairs
+        .iter()
+        .map(|(key, value)| (key.as_bytes().to_vec(), value.as_bytes().to_vec()))
+        .collect()
+}
+
+#[test]
+fn empty_trie_root_is_the_hash_of_the_empty_byte_array() {
+    assert_eq!(trie_root(&[]), keccak256(&[0x80]));
+    assert_eq!(trie_root(&[]), EMPTY_TRIE_ROOT);
+    assert_eq!(
+        EMPTY_TRIE_ROOT,
+        u256("56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421")
+    );
+}
+
+#[test]
+fn trie_root_of_known_tries() {
+    // leaves, extensions and a branch with a value
+    let puppy = items(&[
+        ("do", "verb"),
+        ("horse", "stallion"),
+        ("doge", "coin"),
+        ("dog", "puppy"),
+    ]);
+    assert_eq!(
+        trie_root(&puppy),
+        u256("5991bb8c6514148a29db676a14ac506cd2cd5775ace63c30a4fe457715e9ac84")
+    );
+
+    let dogs = items(&[
+        ("doe", "reindeer"),
+        ("dog", "puppy"),
+        ("dogglesworth", "cat"),
+    ]);
+    assert_eq!(
+        trie_root(&dogs),
+        u256("8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3")
+    );
+}
+
+#[test]
+fn trie_root_does_not_depend_on_the_order_of_the_items() {
+    let mut dogs = items(&[
+        ("doe", "reindeer"),
+        ("dog", "puppy"),
+        ("dogglesworth", "cat"),
+    ]);
+    let root = trie_root(&dogs);
+    dogs.reverse();
+    assert_eq!(trie_root(&dogs), root);
+}
+
+#[test]
+fn storage_root_leaves_out_zero_slots() {
+    assert_eq!(storage_root(&HashMap::new()), EMPTY_TRIE_ROOT);
+    assert_eq!(
+        storage_root(&HashMap::from([(U256::one(), U256::zero())])),
+        EMPTY_TRIE_ROOT
+    );
+
+    let storage = HashMap::from([(U256::zero(), U256::from(0x2a))]);
+    let mut key = [0_u8; 32];
+    U256::zero().to_big_endian(&mut key);
+    let mut hashed_key = [0_u8; 32];
+    keccak256(&key).to_big_endian(&mut hashed_key);
+    assert_eq!(
+        storage_root(&storage),
+        trie_root(&[(hashed_key.to_vec(), rlp::encode_u256(U256::from(0x2a)))])
+    );
+}
+
+#[test]
+fn state_root_of_known_states() {
+    assert_eq!(state_root(&GlobalState::new()), EMPTY_TRIE_ROOT);
+
+    // the state after the transaction of the LOG1 state test fixture
+    let code = hex::decode("60ff60005360aa60016000a1").unwrap();
+    let balance = U256::from(1_000_000_000_000_000_000_u64);
+    let state = GlobalState::from([
+        (
+            u256("095e7baea6a6c7c4c2dfeb977efac326af552d87"),
+            AccountState {
+                nonce: 0,
+                balance: balance + 1,
+                code_hash: keccak256(&code),
+                storage: HashMap::new(),
+            },
+        ),
+        (
+            u256("a94f5374fce5edbc8e2a8697c15331677e6ebf0b"),
+            AccountState {
+                nonce: 1,
+                balance: balance - 217_790 - 1,
+                code_hash: KECCAK_EMPTY,
+                storage: HashMap::new(),
+            },
+        ),
+    ]);
+    assert_eq!(
+        state_root(&state),
+        u256("7ff1a421bf3c088d8044f8ecc91c90e1f03df81d896975a548335811401bef04")
+    );
+}
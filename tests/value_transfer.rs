@@ -0,0 +1,76 @@
+mod common;
+
+use common::{balance, context, execute, install, RECIPIENT};
+use evm_from_scratch_new::evm::utils::{helpers::create_address, spec::SpecId};
+use primitive_types::U256;
+
+const CALLEE: U256 = U256([0xbeef, 0, 0, 0]);
+
+#[test]
+fn call_sends_value_to_the_callee() {
+    let mut ctx = context(SpecId::Cancun);
+    install(&mut ctx, RECIPIENT, 10, "");
+
+    // CALL 0xbeef with 3 wei
+    let result = execute(&mut ctx, "6000600060006000600361beef5af1", 100_000);
+    assert!(result.success);
+    assert_eq!(ctx.machine_state.stack, [U256::one()]);
+    assert_eq!(balance(&ctx, RECIPIENT), U256::from(7));
+    assert_eq!(balance(&ctx, CALLEE), U256::from(3));
+}
+
+#[test]
+fn call_with_more_value_than_the_balance_fails_without_halting() {
+    let mut ctx = context(SpecId::Cancun);
+    install(&mut ctx, RECIPIENT, 10, "");
+
+    // CALL 0xbeef with 11 wei
+    let result = execute(&mut ctx, "6000600060006000600b61beef5af1", 100_000);
+    assert!(result.success);
+    assert_eq!(ctx.machine_state.stack, [U256::zero()]);
+    assert_eq!(balance(&ctx, RECIPIENT), U256::from(10));
+    assert_eq!(balance(&ctx, CALLEE), U256::zero());
+}
+
+#[test]
+fn failed_call_clears_the_return_data_of_the_previous_one() {
+    let mut ctx = context(SpecId::Cancun);
+    install(&mut ctx, RECIPIENT, 10, "");
+
+    // CALL the identity precompile with one byte and RETURNDATASIZE, then
+    // CALL 0xbeef with 11 wei, which fails before running, and RETURNDATASIZE
+    let code = [
+        "6000600060016000600060045af1503d",
+        "6000600060006000600b61beef5af150",
+        "3d",
+    ]
+    .concat();
+    let result = execute(&mut ctx, &code, 100_000);
+    assert!(result.success);
+    assert_eq!(ctx.machine_state.stack, [U256::one(), U256::zero()]);
+}
+
+#[test]
+fn returndatacopy_out_of_bounds_is_an_exceptional_halt() {
+    let mut ctx = context(SpecId::Cancun);
+
+    // RETURNDATACOPY of one byte, while there is no return data
+    let result = execute(&mut ctx, "6001600060003e", 100_000);
+    assert!(!result.success);
+    assert_eq!(result.gas_used, U256::from(100_000));
+}
+
+#[test]
+fn create_sends_value_to_the_new_account() {
+    let mut ctx = context(SpecId::Cancun);
+    install(&mut ctx, RECIPIENT, 10, "");
+
+    // CREATE with 3 wei and no initialisation code
+    let result = execute(&mut ctx, "600060006003f0", 100_000);
+    assert!(result.success);
+
+    let address = create_address(RECIPIENT, 0);
+    assert_eq!(ctx.machine_state.stack, [address]);
+    assert_eq!(balance(&ctx, RECIPIENT), U256::from(7));
+    assert_eq!(balance(&ctx, address), U256::from(3));
+}